@@ -0,0 +1,249 @@
+//! Conversion of [CSL-JSON](https://citeproc-js.readthedocs.io/en/latest/csl-json/markup.html)
+//! items, as exported by Zotero and many other tools.
+
+use super::{set_field, set_names, Name};
+use biblatex::{Bibliography, Entry, EntryType};
+use serde_json::Value;
+use std::error::Error;
+
+pub fn parse(content: &str) -> Result<Bibliography, Box<dyn Error>> {
+    let value: Value = serde_json::from_str(content)?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(_) => std::slice::from_ref(&value),
+        _ => return Err("expected an array of CSL-JSON items".into()),
+    };
+
+    let mut bibliography = Bibliography::new();
+    for item in items {
+        bibliography.insert(convert_item(item)?);
+    }
+    Ok(bibliography)
+}
+
+fn convert_item(item: &Value) -> Result<Entry, Box<dyn Error>> {
+    let key = match item.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        Some(Value::Number(id)) => id.to_string(),
+        _ => return Err("CSL-JSON item without an id".into()),
+    };
+    let csl_type = item.get("type").and_then(|t| t.as_str()).unwrap_or("");
+    let entry_type = entry_type(csl_type);
+    let is_article = entry_type == EntryType::Article;
+    let mut entry = Entry::new(key, entry_type);
+
+    for (csl, field) in [
+        ("title", "title"),
+        ("title-short", "shorttitle"),
+        ("collection-title", "series"),
+        ("publisher", "publisher"),
+        ("publisher-place", "address"),
+        ("volume", "volume"),
+        ("number", "number"),
+        ("edition", "edition"),
+        ("page", "pages"),
+        ("chapter-number", "chapter"),
+        ("number-of-pages", "pagetotal"),
+        ("DOI", "doi"),
+        ("URL", "url"),
+        ("ISBN", "isbn"),
+        ("ISSN", "issn"),
+        ("note", "note"),
+        ("abstract", "abstract"),
+        ("genre", "type"),
+        ("event", "eventtitle"),
+        ("event-title", "eventtitle"),
+        ("version", "version"),
+        ("language", "language"),
+        ("container-title-short", "shortjournal"),
+    ]
+    .iter()
+    {
+        if let Some(value) = text(item, csl) {
+            set_field(&mut entry, field, &value);
+        }
+    }
+
+    if let Some(container) = text(item, "container-title") {
+        let field = if is_article { "journal" } else { "booktitle" };
+        set_field(&mut entry, field, &container);
+    }
+    if let Some(issue) = text(item, "issue") {
+        set_field(&mut entry, "number", &issue);
+    }
+
+    for (csl, field) in [
+        ("author", "author"),
+        ("editor", "editor"),
+        ("translator", "translator"),
+        ("container-author", "bookauthor"),
+    ]
+    .iter()
+    {
+        if let Some(Value::Array(names)) = item.get(csl) {
+            let names: Vec<_> = names.iter().map(convert_name).collect();
+            set_names(&mut entry, field, &names);
+        }
+    }
+
+    for (csl, field) in [
+        ("issued", "date"),
+        ("accessed", "urldate"),
+        ("original-date", "origdate"),
+        ("event-date", "eventdate"),
+    ]
+    .iter()
+    {
+        if let Some(date) = item.get(csl).and_then(convert_date) {
+            set_field(&mut entry, field, &date);
+        }
+    }
+
+    Ok(entry)
+}
+
+/// Reads a text or number variable.
+fn text(item: &Value, variable: &str) -> Option<String> {
+    match item.get(variable)? {
+        Value::String(s) => Some(s.replace('–', "-")),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn convert_name(name: &Value) -> Name {
+    let part = |key: &str| {
+        name.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_owned()
+    };
+    if let Some(literal) = name.get("literal").and_then(|v| v.as_str()) {
+        return Name {
+            literal: Some(literal.to_owned()),
+            ..Name::default()
+        };
+    }
+    let prefix = [part("dropping-particle"), part("non-dropping-particle")]
+        .iter()
+        .filter(|p| !p.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    Name {
+        family: part("family"),
+        given: part("given"),
+        prefix,
+        suffix: part("suffix"),
+        literal: None,
+    }
+}
+
+/// Converts a CSL-JSON date into an ISO 8601 (range) string, as used by BibLaTeX.
+fn convert_date(date: &Value) -> Option<String> {
+    if let Some(Value::Array(ranges)) = date.get("date-parts") {
+        let parts: Vec<_> = ranges
+            .iter()
+            .filter_map(|parts| {
+                let parts = parts.as_array()?;
+                let number = |idx: usize| {
+                    parts.get(idx).and_then(|p| match p {
+                        Value::Number(n) => n.as_i64(),
+                        Value::String(s) => s.parse().ok(),
+                        _ => None,
+                    })
+                };
+                let mut result = format!("{:04}", number(0)?);
+                if let Some(month) = number(1) {
+                    result.push_str(&format!("-{:02}", month));
+                    if let Some(day) = number(2) {
+                        result.push_str(&format!("-{:02}", day));
+                    }
+                }
+                Some(result)
+            })
+            .collect();
+        if !parts.is_empty() {
+            return Some(parts.join("/"));
+        }
+    }
+    date.get("raw")
+        .or_else(|| date.get("literal"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_owned())
+}
+
+fn entry_type(csl_type: &str) -> EntryType {
+    match csl_type {
+        "article-journal" | "article-magazine" | "article-newspaper" | "article" => {
+            EntryType::Article
+        }
+        "book" => EntryType::Book,
+        "chapter" => EntryType::InCollection,
+        "paper-conference" => EntryType::InProceedings,
+        "report" => EntryType::Report,
+        "thesis" => EntryType::Thesis,
+        "webpage" | "post" | "post-weblog" => EntryType::Online,
+        "manuscript" => EntryType::Unpublished,
+        "patent" => EntryType::Patent,
+        "software" => EntryType::Software,
+        "dataset" => EntryType::Dataset,
+        "entry-encyclopedia" | "entry-dictionary" | "entry" => EntryType::InReference,
+        "periodical" => EntryType::Periodical,
+        _ => EntryType::Misc,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::format::format_chunk;
+    use biblatex::{ChunksExt, EntryType};
+
+    #[test]
+    fn parse_csl_json() {
+        let bib = super::parse(
+            r#"[
+  {
+    "id": "Knuth1984",
+    "type": "article-journal",
+    "title": "Literate Programming",
+    "container-title": "The Computer Journal",
+    "volume": 27,
+    "issue": "2",
+    "page": "97-111",
+    "DOI": "10.1093/comjnl/27.2.97",
+    "author": [{"family": "Knuth", "given": "Donald E."}],
+    "issued": {"date-parts": [[1984, 2]]}
+  },
+  {
+    "id": "WHO2020",
+    "type": "report",
+    "title": "World health statistics -- costs in $ and ~%",
+    "author": [{"literal": "World Health Organization"}],
+    "issued": {"date-parts": [["2020"]]}
+  }
+]"#,
+        )
+        .unwrap();
+
+        let entry = bib.get("Knuth1984").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Article);
+        assert_eq!(
+            entry.journal().unwrap().format_verbatim(),
+            "The Computer Journal"
+        );
+        assert_eq!(entry.volume(), Some(27));
+        assert_eq!(entry.get("pages").unwrap().format_verbatim(), "97-111");
+        assert_eq!(entry.author().unwrap()[0].name, "Knuth");
+        assert_eq!(entry.author().unwrap()[0].given_name, "Donald E.");
+        assert_eq!(entry.get("date").unwrap().format_verbatim(), "1984-02");
+
+        let entry = bib.get("WHO2020").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Report);
+        assert_eq!(entry.author().unwrap()[0].name, "World Health Organization");
+        assert_eq!(
+            format_chunk(entry.title().unwrap()),
+            "World health statistics -- costs in $ and ~%"
+        );
+    }
+}
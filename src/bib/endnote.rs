@@ -0,0 +1,301 @@
+//! Conversion of EndNote XML exports.
+
+use super::{generate_key, set_field, set_names, Name};
+use biblatex::{Bibliography, Entry, EntryType};
+use roxmltree::Node;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+/// Elements that carry no bibliographic information.
+const IGNORED: &[&str] = &["rec-number", "foreign-keys", "database", "source-app"];
+
+/// Parses EndNote XML records. Returns the bibliography and warnings about unmapped elements.
+pub fn parse(content: &str) -> Result<(Bibliography, Vec<String>), Box<dyn Error>> {
+    let doc = roxmltree::Document::parse(content)?;
+    if !doc.root_element().has_tag_name("xml") {
+        return Err("root element must be <xml>".into());
+    }
+
+    let mut bibliography = Bibliography::new();
+    let mut unmapped = BTreeSet::new();
+    for record in doc.descendants().filter(|n| n.has_tag_name("record")) {
+        let entry = convert_record(record, &bibliography, &mut unmapped);
+        bibliography.insert(entry);
+    }
+
+    let warnings = unmapped
+        .into_iter()
+        .map(|element| format!("unmapped EndNote element <{}>", element))
+        .collect();
+    Ok((bibliography, warnings))
+}
+
+fn convert_record(
+    record: Node,
+    bibliography: &Bibliography,
+    unmapped: &mut BTreeSet<String>,
+) -> Entry {
+    let entry_type = record
+        .children()
+        .find(|n| n.has_tag_name("ref-type"))
+        .map(|n| entry_type(n.attribute("name").unwrap_or_default(), &text(n)))
+        .unwrap_or(EntryType::Misc);
+    let is_article = entry_type == EntryType::Article;
+    let is_book = matches!(entry_type, EntryType::Book | EntryType::InCollection);
+
+    let mut key = None;
+    let mut names: Vec<(&str, Vec<Name>)> = vec![];
+    let mut keywords = vec![];
+    let mut fields: Vec<(&str, String)> = vec![];
+
+    for node in record.children().filter(|n| n.is_element()) {
+        let name = node.tag_name().name();
+        match name {
+            "ref-type" => {}
+            "label" => {
+                let label = text(node);
+                if !label.is_empty() && !label.contains(char::is_whitespace) {
+                    key = Some(label);
+                }
+            }
+            "contributors" => {
+                for group in node.children().filter(|n| n.is_element()) {
+                    let field = match group.tag_name().name() {
+                        "authors" => "author",
+                        "secondary-authors" => "editor",
+                        "translated-authors" => "translator",
+                        other => {
+                            unmapped.insert(format!("contributors/{}", other));
+                            continue;
+                        }
+                    };
+                    let list = group
+                        .children()
+                        .filter(|n| n.has_tag_name("author"))
+                        .map(|n| Name::parse(&text(n)))
+                        .collect();
+                    names.push((field, list));
+                }
+            }
+            "titles" => {
+                for title in node.children().filter(|n| n.is_element()) {
+                    let field = match title.tag_name().name() {
+                        "title" => "title",
+                        "secondary-title" if is_article => "journal",
+                        "secondary-title" => "booktitle",
+                        "tertiary-title" => "series",
+                        "short-title" => "shorttitle",
+                        "alt-title" => "shortjournal",
+                        other => {
+                            unmapped.insert(format!("titles/{}", other));
+                            continue;
+                        }
+                    };
+                    fields.push((field, text(title)));
+                }
+            }
+            "periodical" => {
+                for title in node.children().filter(|n| n.is_element()) {
+                    match title.tag_name().name() {
+                        "full-title" => fields.push(("journal", text(title))),
+                        "abbr-1" => fields.push(("shortjournal", text(title))),
+                        other => {
+                            unmapped.insert(format!("periodical/{}", other));
+                        }
+                    }
+                }
+            }
+            "dates" => {
+                let year = child_text(node, "year");
+                let date = node
+                    .descendants()
+                    .find(|n| n.has_tag_name("date"))
+                    .and_then(|n| convert_date(&text(n), year.as_deref()));
+                if let Some(date) = date.or(year) {
+                    fields.push(("date", date));
+                }
+            }
+            "urls" => {
+                if let Some(url) = node.descendants().find(|n| n.has_tag_name("url")) {
+                    fields.push(("url", text(url)));
+                }
+            }
+            "keywords" => {
+                keywords.extend(
+                    node.children()
+                        .filter(|n| n.has_tag_name("keyword"))
+                        .map(text),
+                );
+            }
+            "isbn" => fields.push((if is_book { "isbn" } else { "issn" }, text(node))),
+            _ => match simple_field(name) {
+                Some(field) => fields.push((field, text(node).replace('–', "-"))),
+                None if IGNORED.contains(&name) => {}
+                None => {
+                    unmapped.insert(name.to_owned());
+                }
+            },
+        }
+    }
+
+    let authors = names
+        .iter()
+        .find(|(field, _)| *field == "author")
+        .map_or(&[][..], |(_, list)| &list[..]);
+    let date = fields
+        .iter()
+        .find(|(field, _)| *field == "date")
+        .map(|(_, date)| date.as_str());
+    let key = key.unwrap_or_else(|| generate_key(authors, date, bibliography));
+    let mut entry = Entry::new(key, entry_type);
+
+    for (field, list) in &names {
+        set_names(&mut entry, field, list);
+    }
+    set_field(&mut entry, "keywords", &keywords.join(", "));
+    for (field, value) in fields {
+        if entry.get(field).is_none() {
+            set_field(&mut entry, field, &value);
+        }
+    }
+
+    entry
+}
+
+fn simple_field(element: &str) -> Option<&'static str> {
+    Some(match element {
+        "pages" => "pages",
+        "volume" => "volume",
+        "number" => "number",
+        "edition" => "edition",
+        "publisher" => "publisher",
+        "pub-location" => "address",
+        "electronic-resource-num" => "doi",
+        "abstract" => "abstract",
+        "notes" => "note",
+        "language" => "language",
+        "work-type" => "type",
+        "section" => "chapter",
+        _ => return None,
+    })
+}
+
+/// The text of an element, including all nested `<style>` elements.
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|n| n.has_tag_name(name))
+        .map(text)
+        .filter(|t| !t.is_empty())
+}
+
+/// Converts an EndNote publication date (`YYYY-MM-DD` or `MM/DD/YYYY`) into ISO 8601.
+fn convert_date(date: &str, year: Option<&str>) -> Option<String> {
+    let numbers: Vec<u32> = date
+        .split(&['-', '/'][..])
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [y, m, d] if y > 31 => Some(format!("{:04}-{:02}-{:02}", y, m, d)),
+        [m, d, y] if y > 31 => Some(format!("{:04}-{:02}-{:02}", y, m, d)),
+        [y, m] if y > 31 => Some(format!("{:04}-{:02}", y, m)),
+        [m] if m <= 12 => year.map(|y| format!("{}-{:02}", y, m)),
+        _ => None,
+    }
+}
+
+fn entry_type(name: &str, number: &str) -> EntryType {
+    match (name, number) {
+        ("Journal Article", _) | ("Magazine Article", _) | ("Newspaper Article", _) => {
+            EntryType::Article
+        }
+        (_, "17") | (_, "19") | (_, "23") => EntryType::Article,
+        ("Book", _) | ("Edited Book", _) | (_, "6") | (_, "28") => EntryType::Book,
+        ("Book Section", _) | (_, "5") => EntryType::InCollection,
+        ("Conference Paper", _) | (_, "47") => EntryType::InProceedings,
+        ("Conference Proceedings", _) | (_, "10") => EntryType::Proceedings,
+        ("Report", _) | (_, "27") => EntryType::Report,
+        ("Thesis", _) | (_, "32") => EntryType::Thesis,
+        ("Web Page", _) | (_, "12") => EntryType::Online,
+        ("Unpublished Work", _) | (_, "34") => EntryType::Unpublished,
+        ("Patent", _) | (_, "25") => EntryType::Patent,
+        ("Computer Program", _) | (_, "9") => EntryType::Software,
+        ("Dataset", _) | (_, "59") => EntryType::Dataset,
+        _ => EntryType::Misc,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use biblatex::{ChunksExt, EntryType};
+
+    #[test]
+    fn parse_endnote() {
+        let (bib, warnings) = super::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<xml><records>
+<record>
+  <rec-number>1</rec-number>
+  <ref-type name="Journal Article">17</ref-type>
+  <contributors><authors>
+    <author><style face="normal" font="default" size="100%">Knuth, Donald E.</style></author>
+  </authors></contributors>
+  <titles>
+    <title><style face="normal" font="default" size="100%">Literate Programming</style></title>
+    <secondary-title>The Computer Journal</secondary-title>
+  </titles>
+  <pages>97-111</pages>
+  <volume>27</volume>
+  <number>2</number>
+  <dates><year>1984</year><pub-dates><date>02</date></pub-dates></dates>
+  <electronic-resource-num>10.1093/comjnl/27.2.97</electronic-resource-num>
+  <research-notes>read</research-notes>
+</record>
+<record>
+  <ref-type name="Book">6</ref-type>
+  <contributors><authors>
+    <author>Klabnik, Steve</author>
+    <author>Nichols, Carol</author>
+  </authors></contributors>
+  <titles><title>The Rust Programming Language</title></titles>
+  <dates><year>2018</year></dates>
+  <label>Klabnik2018</label>
+</record>
+</records></xml>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            warnings,
+            vec!["unmapped EndNote element <research-notes>".to_string()]
+        );
+
+        let entry = bib.get("Knuth1984").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Article);
+        assert_eq!(
+            entry.journal().unwrap().format_verbatim(),
+            "The Computer Journal"
+        );
+        assert_eq!(
+            entry.title().unwrap().format_verbatim(),
+            "Literate Programming"
+        );
+        assert_eq!(entry.get("date").unwrap().format_verbatim(), "1984-02");
+        assert_eq!(
+            entry.get("doi").unwrap().format_verbatim(),
+            "10.1093/comjnl/27.2.97"
+        );
+
+        let entry = bib.get("Klabnik2018").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Book);
+        assert_eq!(entry.author().unwrap().len(), 2);
+    }
+}
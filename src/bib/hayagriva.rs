@@ -0,0 +1,274 @@
+//! Conversion of [Hayagriva](https://github.com/typst/hayagriva) YAML bibliographies.
+
+use super::{set_field, set_names, Name};
+use biblatex::{Bibliography, Entry, EntryType};
+use serde_yaml::Value;
+use std::error::Error;
+
+pub fn parse(content: &str) -> Result<Bibliography, Box<dyn Error>> {
+    let value: Value = serde_yaml::from_str(content)?;
+    let entries = value
+        .as_mapping()
+        .ok_or("expected a mapping of citation keys to entries")?;
+
+    let mut bibliography = Bibliography::new();
+    for (key, entry) in entries {
+        let key = scalar(key).ok_or("expected a string as citation key")?;
+        bibliography.insert(convert_entry(key, entry));
+    }
+    Ok(bibliography)
+}
+
+fn convert_entry(key: String, item: &Value) -> Entry {
+    let parent = match field(item, "parent") {
+        Some(Value::Sequence(parents)) => parents.first(),
+        other => other,
+    };
+    let parent_type = parent
+        .and_then(|p| field(p, "type"))
+        .and_then(scalar)
+        .unwrap_or_default()
+        .to_lowercase();
+    let entry_type = entry_type(
+        &field(item, "type")
+            .and_then(scalar)
+            .unwrap_or_default()
+            .to_lowercase(),
+        &parent_type,
+    );
+    let is_article = entry_type == EntryType::Article;
+    let mut entry = Entry::new(key, entry_type);
+
+    if let Some(title) = field(item, "title").and_then(formattable) {
+        set_field(&mut entry, "title", &title);
+    }
+    for (hayagriva, bib) in [("author", "author"), ("editor", "editor")].iter() {
+        if let Some(names) = field(item, hayagriva).map(convert_names) {
+            set_names(&mut entry, bib, &names);
+        }
+    }
+    set_simple_fields(&mut entry, item);
+
+    if let Some(parent) = parent {
+        if let Some(title) = field(parent, "title").and_then(formattable) {
+            let bib = if is_article { "journal" } else { "booktitle" };
+            set_field(&mut entry, bib, &title);
+        }
+        if let Some(names) = field(parent, "editor").map(convert_names) {
+            set_names(&mut entry, "editor", &names);
+        }
+        set_simple_fields(&mut entry, parent);
+    }
+
+    entry
+}
+
+/// Sets the fields that are shared between entries and their parents,
+/// without overwriting fields that are already set.
+fn set_simple_fields(entry: &mut Entry, item: &Value) {
+    for (hayagriva, bib) in [
+        ("date", "date"),
+        ("location", "address"),
+        ("organization", "organization"),
+        ("page-range", "pages"),
+        ("volume", "volume"),
+        ("issue", "number"),
+        ("edition", "edition"),
+        ("genre", "type"),
+        ("note", "note"),
+        ("abstract", "abstract"),
+        ("language", "language"),
+    ]
+    .iter()
+    {
+        if entry.get(bib).is_none() {
+            if let Some(value) = field(item, hayagriva).and_then(formattable) {
+                set_field(entry, bib, &value.replace('–', "-"));
+            }
+        }
+    }
+
+    if entry.get("publisher").is_none() {
+        match field(item, "publisher") {
+            Some(publisher @ Value::Mapping(_)) => {
+                if let Some(name) = field(publisher, "name").and_then(formattable) {
+                    set_field(entry, "publisher", &name);
+                }
+                if let Some(location) = field(publisher, "location").and_then(formattable) {
+                    set_field(entry, "address", &location);
+                }
+            }
+            Some(publisher) => {
+                if let Some(name) = formattable(publisher) {
+                    set_field(entry, "publisher", &name);
+                }
+            }
+            None => {}
+        }
+    }
+
+    if entry.get("url").is_none() {
+        if let Some(url) = field(item, "url") {
+            if let Some(value) = formattable(url) {
+                set_field(entry, "url", &value);
+            }
+            if let Some(date) = field(url, "date").and_then(scalar) {
+                set_field(entry, "urldate", &date);
+            }
+        }
+    }
+
+    match field(item, "serial-number") {
+        Some(Value::Mapping(numbers)) => {
+            for (key, value) in numbers {
+                let bib = match scalar(key).unwrap_or_default().to_lowercase().as_str() {
+                    "doi" => "doi",
+                    "isbn" => "isbn",
+                    "issn" => "issn",
+                    "arxiv" => "eprint",
+                    _ => continue,
+                };
+                if let (None, Some(value)) = (entry.get(bib), scalar(value)) {
+                    set_field(entry, bib, &value);
+                    if bib == "eprint" {
+                        set_field(entry, "eprinttype", "arxiv");
+                    }
+                }
+            }
+        }
+        Some(number) => {
+            if let Some(number) = scalar(number) {
+                if entry.get("doi").is_none() {
+                    set_field(entry, "doi", &number);
+                }
+            }
+        }
+        None => {}
+    }
+}
+
+fn field<'a>(item: &'a Value, key: &str) -> Option<&'a Value> {
+    item.as_mapping()?.get(&Value::String(key.to_owned()))
+}
+
+/// Reads a string, number or boolean value as a string.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads a formattable string, given either directly or as `value` of a mapping.
+fn formattable(value: &Value) -> Option<String> {
+    match value {
+        Value::Mapping(_) => field(value, "value").and_then(scalar),
+        _ => scalar(value),
+    }
+}
+
+fn convert_names(value: &Value) -> Vec<Name> {
+    match value {
+        Value::Sequence(names) => names.iter().filter_map(convert_name).collect(),
+        _ => convert_name(value).into_iter().collect(),
+    }
+}
+
+fn convert_name(value: &Value) -> Option<Name> {
+    match value {
+        Value::Mapping(_) => {
+            let part = |key| field(value, key).and_then(scalar).unwrap_or_default();
+            Some(Name {
+                family: part("name"),
+                given: part("given-name"),
+                prefix: part("prefix"),
+                suffix: part("suffix"),
+                literal: None,
+            })
+        }
+        _ => scalar(value).map(|name| Name::parse(&name)),
+    }
+}
+
+fn entry_type(hayagriva_type: &str, parent_type: &str) -> EntryType {
+    match hayagriva_type {
+        "article" if parent_type == "proceedings" || parent_type == "conference" => {
+            EntryType::InProceedings
+        }
+        "article" => EntryType::Article,
+        "book" => EntryType::Book,
+        "chapter" | "anthos" => EntryType::InCollection,
+        "thesis" => EntryType::Thesis,
+        "report" => EntryType::Report,
+        "web" => EntryType::Online,
+        "proceedings" => EntryType::Proceedings,
+        "repository" => EntryType::Software,
+        "manuscript" => EntryType::Unpublished,
+        "patent" => EntryType::Patent,
+        "reference" => EntryType::Reference,
+        "entry" => EntryType::InReference,
+        "periodical" => EntryType::Periodical,
+        "anthology" => EntryType::Collection,
+        _ => EntryType::Misc,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use biblatex::{ChunksExt, EntryType};
+
+    #[test]
+    fn parse_hayagriva() {
+        let bib = super::parse(
+            r#"
+Knuth1984:
+  type: Article
+  title: Literate Programming
+  author: Knuth, Donald E.
+  date: 1984-02
+  page-range: 97-111
+  parent:
+    type: Periodical
+    title: The Computer Journal
+    volume: 27
+    issue: 2
+  serial-number:
+    doi: 10.1093/comjnl/27.2.97
+
+Klabnik2018:
+  type: Book
+  title: The Rust Programming Language
+  author: ["Klabnik, Steve", "Nichols, Carol"]
+  date: 2018
+  publisher:
+    name: No Starch Press
+    location: San Francisco
+"#,
+        )
+        .unwrap();
+
+        let entry = bib.get("Knuth1984").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Article);
+        assert_eq!(
+            entry.journal().unwrap().format_verbatim(),
+            "The Computer Journal"
+        );
+        assert_eq!(entry.volume(), Some(27));
+        assert_eq!(entry.get("pages").unwrap().format_verbatim(), "97-111");
+        assert_eq!(entry.author().unwrap()[0].given_name, "Donald E.");
+        assert_eq!(
+            entry.get("doi").unwrap().format_verbatim(),
+            "10.1093/comjnl/27.2.97"
+        );
+
+        let entry = bib.get("Klabnik2018").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Book);
+        assert_eq!(entry.author().unwrap().len(), 2);
+        assert_eq!(
+            entry.get("address").unwrap().format_verbatim(),
+            "San Francisco"
+        );
+    }
+}
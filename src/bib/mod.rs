@@ -0,0 +1,701 @@
+mod csl_json;
+mod endnote;
+mod hayagriva;
+mod ris;
+
+use crate::config::{BibFormat, Config, DuplicateKeys};
+use crate::diagnostic::{Category, Diagnostics};
+use crate::format::latex;
+use biblatex::{Bibliography, Chunk, Chunks, Entry, RawBibliography};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+static ENTRY_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\A@\s*(\w+)\s*([{(])\s*([^,\s]*)").unwrap());
+
+/// Loads and merges the bibliographies from all files matching the configured paths or glob patterns.
+pub fn load_bibliography(
+    config: &Config,
+    diagnostics: &mut Diagnostics,
+) -> Result<Bibliography, Box<dyn Error>> {
+    let mut sources = vec![];
+    for file in resolve_files(&config.bib_files, diagnostics)? {
+        let content = std::fs::read_to_string(&file).map_err(|err| {
+            format!(
+                "Can't read bibliography from file {} - {}",
+                file.display(),
+                err
+            )
+        })?;
+        sources.push((file, content));
+    }
+
+    merge_bibliographies(&sources, config, diagnostics)
+}
+
+fn resolve_files<S: AsRef<str>>(
+    patterns: &[S],
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = vec![];
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        if pattern.contains(&['*', '?', '['][..]) {
+            let mut matches = glob::glob(pattern)
+                .map_err(|err| format!("Invalid bibliography pattern {} - {}", pattern, err))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if matches.is_empty() {
+                diagnostics.report(
+                    Category::MissingFile,
+                    format!("no bibliography files found for pattern {}", pattern),
+                );
+            }
+            matches.sort();
+            files.extend(matches);
+        } else {
+            files.push(PathBuf::from(pattern));
+        }
+    }
+    Ok(files)
+}
+
+fn merge_bibliographies(
+    sources: &[(PathBuf, String)],
+    config: &Config,
+    diagnostics: &mut Diagnostics,
+) -> Result<Bibliography, Box<dyn Error>> {
+    let mut bibliography = Bibliography::new();
+    let mut locations: HashMap<String, Location> = HashMap::new();
+
+    for (file, content) in sources {
+        let format = config
+            .bib_format
+            .clone()
+            .unwrap_or_else(|| BibFormat::from_path(file));
+        let (bib, warnings) = parse_bibliography(content, &format)
+            .map_err(|err| format!("No valid bibliography in file {} - {}", file.display(), err))?;
+        if format == BibFormat::BibTeX {
+            report_duplicate_entries(file, content, diagnostics);
+        }
+        for warning in warnings {
+            diagnostics.report(
+                Category::IgnoredContent,
+                format!("ignoring {} in file {}", warning, file.display()),
+            );
+        }
+
+        for entry in bib.iter() {
+            let location = Location::find(file, content, &entry.key, &format);
+            if let Some(previous) = locations.get(&entry.key) {
+                let message = format!(
+                    "citation key '{}' in {} and {}",
+                    entry.key, previous, location
+                );
+                match config.duplicate_keys {
+                    DuplicateKeys::Error => {
+                        return Err(format!("Duplicate {}", message).into());
+                    }
+                    DuplicateKeys::FirstWins => {
+                        diagnostics.report(
+                            Category::DuplicateKey,
+                            format!("duplicate {}, using the first one.", message),
+                        );
+                        continue;
+                    }
+                    DuplicateKeys::LastWins => {
+                        diagnostics.report(
+                            Category::DuplicateKey,
+                            format!("duplicate {}, using the last one.", message),
+                        );
+                    }
+                }
+            }
+            locations.insert(entry.key.clone(), location);
+            bibliography.insert(entry.clone());
+        }
+    }
+
+    Ok(bibliography)
+}
+
+/// Parses a bibliography in the given format. Also returns warnings about ignored content.
+fn parse_bibliography(
+    content: &str,
+    format: &BibFormat,
+) -> Result<(Bibliography, Vec<String>), Box<dyn Error>> {
+    match format {
+        // An entry missing a closing brace may be parsed leniently, swallowing the next entries
+        BibFormat::BibTeX => match Bibliography::parse(content) {
+            Some(mut bib)
+                if entry_spans(content)
+                    .iter()
+                    .all(|(span, _, _)| balanced(&content[span.clone()])) =>
+            {
+                keep_latex(&mut bib, content);
+                Ok((bib, vec![]))
+            }
+            _ => Err(bibtex_error(content).into()),
+        },
+        BibFormat::CslJson => csl_json::parse(content).map(|bib| (bib, vec![])),
+        BibFormat::Hayagriva => hayagriva::parse(content).map(|bib| (bib, vec![])),
+        BibFormat::Ris => ris::parse(content),
+        BibFormat::EndNoteXml => endnote::parse(content),
+    }
+}
+
+/// Replaces the values of fields with LaTeX markup by their source.
+///
+/// biblatex resolves accents, but drops other commands like `\emph` and unescapes `\$`,
+/// so that the markup can't be converted for output any more.
+fn keep_latex(bib: &mut Bibliography, content: &str) {
+    let raw = RawBibliography::parse(content);
+    let mut done = HashSet::new();
+    // Of entries with the same key, biblatex keeps the last one
+    for raw_entry in raw.entries.iter().rev() {
+        if !done.insert(raw_entry.key) {
+            continue;
+        }
+        let entry = match bib.get_mut(raw_entry.key) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        for (field, value) in &raw_entry.fields {
+            if !value.contains(&['\\', '$'][..])
+                || RAW_FIELDS.contains(&field.to_lowercase().as_str())
+            {
+                continue;
+            }
+            if let Some(chunks) = latex_chunks(value, &raw.abbreviations) {
+                entry.fields.insert(field.to_string(), chunks);
+            }
+        }
+    }
+}
+
+/// Splits a raw field value into chunks that keep the LaTeX source: text in braces
+/// becomes verbatim chunks, including the braces. Resolves concatenation with `#`
+/// and `@string` abbreviations. Returns `None` for values that can't be resolved.
+fn latex_chunks(value: &str, abbreviations: &HashMap<&str, &str>) -> Option<Chunks> {
+    let mut chunks = vec![];
+    for part in split_concatenation(value) {
+        let part = part.trim();
+        let text = if (part.starts_with('{') && part.ends_with('}'))
+            || (part.len() >= 2 && part.starts_with('"') && part.ends_with('"'))
+        {
+            &part[1..part.len() - 1]
+        } else if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) {
+            part
+        } else {
+            chunks.extend(latex_chunks(abbreviations.get(part)?, abbreviations)?);
+            continue;
+        };
+
+        let mut depth = 0;
+        let mut escaped = false;
+        let mut current = String::new();
+        for c in text.chars() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '{' => {
+                    if depth == 0 && !current.is_empty() {
+                        chunks.push(Chunk::Normal(std::mem::take(&mut current)));
+                    }
+                    depth += 1;
+                }
+                '}' if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        current.push(c);
+                        chunks.push(Chunk::Verbatim(std::mem::take(&mut current)));
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        if !current.is_empty() {
+            chunks.push(Chunk::Normal(current));
+        }
+    }
+    Some(chunks)
+}
+
+/// Splits a raw field value at `#` outside of braces and quotes.
+fn split_concatenation(value: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' if depth == 0 => quoted = !quoted,
+            '#' if depth == 0 && !quoted => {
+                parts.push(&value[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Locates the first entry that makes a BibTeX file fail to parse, or has unbalanced braces.
+///
+/// Parses increasingly long prefixes of the file, entry by entry,
+/// so that `@string` definitions remain available to later entries.
+fn bibtex_error(content: &str) -> String {
+    for (span, _, key) in entry_spans(content) {
+        if !balanced(&content[span.clone()]) || Bibliography::parse(&content[..span.end]).is_none()
+        {
+            let line = content[..span.start].matches('\n').count() + 1;
+            return format!(
+                "invalid BibTeX entry '{}' at line {} (byte {})",
+                key, line, span.start
+            );
+        }
+    }
+    "invalid BibTeX".to_owned()
+}
+
+/// The byte ranges of the entries in a BibTeX file, from `@` to the matching closing brace,
+/// with their types and keys.
+///
+/// Like in BibTeX, text between entries is ignored, and so is an `@` after a `%` comment sign.
+/// An entry without a matching closing brace extends to the end of the file.
+fn entry_spans(content: &str) -> Vec<(Range<usize>, &str, &str)> {
+    let mut spans = vec![];
+    let mut pos = 0;
+    let mut comment = false;
+    while let Some(c) = content[pos..].chars().next() {
+        match c {
+            '%' => comment = true,
+            '\n' | '\r' => comment = false,
+            '@' if !comment => {
+                if let Some(cap) = ENTRY_REGEX.captures(&content[pos..]) {
+                    let open = cap.get(2).unwrap();
+                    let close = if open.as_str() == "(" { ')' } else { '}' };
+                    let end = entry_end(content, pos + open.end(), close);
+                    spans.push((
+                        pos..end,
+                        cap.get(1).unwrap().as_str(),
+                        cap.get(3).unwrap().as_str(),
+                    ));
+                    pos = end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        pos += c.len_utf8();
+    }
+    spans
+}
+
+/// The end of an entry, after the closing delimiter that matches its opening one at `start`.
+fn entry_end(content: &str, start: usize, close: char) -> usize {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (idx, c) in content[start..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if c == close && depth == 0 => return start + idx + 1,
+            _ => {}
+        }
+    }
+    content.len()
+}
+
+/// Reports entries of a BibTeX file with the same key. biblatex keeps the last one.
+fn report_duplicate_entries(file: &Path, content: &str, diagnostics: &mut Diagnostics) {
+    let mut lines: HashMap<&str, usize> = HashMap::new();
+    for (span, entry_type, key) in entry_spans(content) {
+        if ["string", "preamble", "comment"].contains(&entry_type.to_lowercase().as_str()) {
+            continue;
+        }
+        let line = content[..span.start].matches('\n').count() + 1;
+        if let Some(previous) = lines.insert(key, line) {
+            let location = |line| Location {
+                file: file.to_owned(),
+                line: Some(line),
+            };
+            diagnostics.report(
+                Category::DuplicateKey,
+                format!(
+                    "duplicate citation key '{}' in {} and {}, using the last one.",
+                    key,
+                    location(previous),
+                    location(line)
+                ),
+            );
+        }
+    }
+}
+
+/// Whether all braces in the text are closed, ignoring escaped braces like `\{`.
+fn balanced(text: &str) -> bool {
+    let mut depth = 0i64;
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+/// A person's name, as given in formats other than BibTeX.
+#[derive(Default)]
+struct Name {
+    family: String,
+    given: String,
+    prefix: String,
+    suffix: String,
+    literal: Option<String>,
+}
+
+impl Name {
+    /// Parses a name in BibTeX-like `Family, Given` or `Given Family` notation.
+    fn parse(name: &str) -> Self {
+        let parts: Vec<_> = name.split(',').map(|p| p.trim().to_owned()).collect();
+        match parts.len() {
+            1 => match name.trim().rsplit_once(' ') {
+                Some((given, family)) => Name {
+                    family: family.to_owned(),
+                    given: given.to_owned(),
+                    ..Name::default()
+                },
+                None => Name {
+                    family: name.trim().to_owned(),
+                    ..Name::default()
+                },
+            },
+            2 => Name {
+                family: parts[0].clone(),
+                given: parts[1].clone(),
+                ..Name::default()
+            },
+            _ => Name {
+                family: parts[0].clone(),
+                suffix: parts[1].clone(),
+                given: parts[2..].join(", "),
+                ..Name::default()
+            },
+        }
+    }
+}
+
+/// Generates a citation key like `Knuth1984` for formats without keys.
+/// Appends `a`, `b`, ... if the key is already used.
+fn generate_key(authors: &[Name], date: Option<&str>, bibliography: &Bibliography) -> String {
+    let name = authors
+        .first()
+        .map(|name| name.literal.as_ref().unwrap_or(&name.family))
+        .map(|name| {
+            name.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Anonymous".to_owned());
+    let year = date
+        .and_then(|date| date.get(..4))
+        .filter(|year| year.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or_default();
+    let key = format!("{}{}", name, year);
+
+    if bibliography.get(&key).is_none() {
+        return key;
+    }
+    ('a'..='z')
+        .map(|suffix| format!("{}{}", key, suffix))
+        .find(|candidate| bibliography.get(candidate).is_none())
+        .unwrap_or(key)
+}
+
+/// Fields that are output without LaTeX conversion, see `format_raw`.
+const RAW_FIELDS: &[&str] = &["url", "doi", "eprint", "isbn", "issn"];
+
+/// Sets a plain text field of an entry.
+///
+/// LaTeX special characters are escaped, so that they are kept as given
+/// when field values are converted from LaTeX for output.
+fn set_field(entry: &mut Entry, field: &str, value: &str) {
+    if !value.is_empty() {
+        let value = if RAW_FIELDS.contains(&field) {
+            value.to_owned()
+        } else {
+            latex::escape(value)
+        };
+        entry.set(field, vec![Chunk::Normal(value)]);
+    }
+}
+
+/// Sets a list of names, in BibTeX notation, as a field of an entry.
+fn set_names(entry: &mut Entry, field: &str, names: &[Name]) {
+    let mut chunks: Chunks = vec![];
+    for (idx, name) in names.iter().enumerate() {
+        if idx > 0 {
+            chunks.push(Chunk::Normal(" and ".to_owned()));
+        }
+        match &name.literal {
+            Some(literal) => chunks.push(Chunk::Verbatim(latex::escape(literal))),
+            None => {
+                let mut text = String::new();
+                if !name.prefix.is_empty() {
+                    text.push_str(&latex::escape(&name.prefix));
+                    text.push(' ');
+                }
+                text.push_str(&latex::escape(&name.family));
+                if !name.suffix.is_empty() {
+                    text.push_str(", ");
+                    text.push_str(&latex::escape(&name.suffix));
+                }
+                if !name.given.is_empty() {
+                    text.push_str(", ");
+                    text.push_str(&latex::escape(&name.given));
+                }
+                chunks.push(Chunk::Normal(text));
+            }
+        }
+    }
+    if !chunks.is_empty() {
+        entry.set(field, chunks);
+    }
+}
+
+/// The file and line where an entry is defined.
+struct Location {
+    file: PathBuf,
+    line: Option<usize>,
+}
+
+impl Location {
+    fn find(file: &Path, content: &str, key: &str, format: &BibFormat) -> Self {
+        let key = regex::escape(key);
+        let pattern = match format {
+            BibFormat::BibTeX => format!(r"@\s*\w+\s*[{{(]\s*{}\s*,", key),
+            BibFormat::CslJson => format!(r#""id"\s*:\s*"?{}\b"#, key),
+            BibFormat::Hayagriva => format!(r#"(?m)^["']?{}["']?\s*:"#, key),
+            BibFormat::Ris => format!(r"(?m)^ID  - {}\s*$", key),
+            BibFormat::EndNoteXml => format!(r"<label>(?:<style[^>]*>)?{}<", key),
+        };
+        let line = Regex::new(&pattern)
+            .ok()
+            .and_then(|re| re.find(content))
+            .map(|m| content[..m.start()].matches('\n').count() + 1);
+        Location {
+            file: file.to_owned(),
+            line,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.file.display(), line),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::{BibFormat, CitationStyle, Config, DuplicateKeys, Level};
+    use crate::diagnostic::Diagnostics;
+    use crate::format;
+    use biblatex::{Bibliography, ChunksExt};
+    use std::error::Error;
+    use std::path::PathBuf;
+
+    fn config(duplicate_keys: DuplicateKeys) -> Config {
+        Config {
+            duplicate_keys,
+            ..Config::default()
+        }
+    }
+
+    fn merge(
+        sources: &[(PathBuf, String)],
+        duplicate_keys: DuplicateKeys,
+    ) -> Result<Bibliography, Box<dyn Error>> {
+        let config = config(duplicate_keys);
+        super::merge_bibliographies(sources, &config, &mut Diagnostics::new(config.levels))
+    }
+
+    fn sources() -> Vec<(PathBuf, String)> {
+        vec![
+            (
+                PathBuf::from("lab.bib"),
+                "@book{A, title = {First A}}\n\n@book{B, title = {B}}\n".to_string(),
+            ),
+            (
+                PathBuf::from("book.bib"),
+                "@book{C, title = {C}}\n@book{A, title = {Second A}}\n".to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn merge_first_wins() {
+        let bib = merge(&sources(), DuplicateKeys::FirstWins).unwrap();
+        assert_eq!(bib.len(), 3);
+        assert_eq!(
+            bib.get("A").unwrap().title().unwrap().format_verbatim(),
+            "First A"
+        );
+    }
+
+    #[test]
+    fn merge_last_wins() {
+        let bib = merge(&sources(), DuplicateKeys::LastWins).unwrap();
+        assert_eq!(bib.len(), 3);
+        assert_eq!(
+            bib.get("A").unwrap().title().unwrap().format_verbatim(),
+            "Second A"
+        );
+    }
+
+    #[test]
+    fn merge_error() {
+        let err = merge(&sources(), DuplicateKeys::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Duplicate citation key 'A' in lab.bib:1 and book.bib:2"
+        );
+    }
+
+    #[test]
+    fn merge_formats() {
+        let sources = vec![
+            (
+                PathBuf::from("lab.bib"),
+                "@book{A, title = {A}}\n".to_string(),
+            ),
+            (
+                PathBuf::from("zotero.json"),
+                r#"[{"id": "B", "type": "book", "title": "B"}]"#.to_string(),
+            ),
+            (
+                PathBuf::from("refs.yml"),
+                "C:\n  type: book\n  title: C\n".to_string(),
+            ),
+            (
+                PathBuf::from("library.ris"),
+                "TY  - BOOK\nID  - D\nTI  - D\nER  - \n".to_string(),
+            ),
+        ];
+        let bib = merge(&sources, DuplicateKeys::Error).unwrap();
+        assert_eq!(bib.len(), 4);
+        assert!(bib.get("D").is_some());
+        assert!(bib.get("B").is_some());
+        assert!(bib.get("C").is_some());
+    }
+
+    #[test]
+    fn bibtex_latex() {
+        let content = r#"
+@string{pub = "Fast \& Safe"}
+@book{A,
+    author = {Str{\"o}m, {\AA}sa},
+    title = {The \textit{Rust} Book on \emph{names}, the $\alpha$-helix, \$5 and \$6},
+    publisher = pub # { Press},
+    year = {2020},
+}
+"#;
+        let (bib, _) = super::parse_bibliography(content, &BibFormat::BibTeX).unwrap();
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            render_key: false,
+            link_refs: false,
+            ..Config::default()
+        };
+        assert_eq!(
+            format::format_reference(bib.get("A").unwrap(), 1, None, &config),
+            "Ström Å (2020): **The *Rust* Book on *names*, the α-helix, $5 and $6**. *Fast & Safe Press*."
+        );
+    }
+
+    #[test]
+    fn bibtex_error() {
+        let sources = vec![(
+            PathBuf::from("lab.bib"),
+            "@book{A, title = {A}}\n\n@book{B, title = {B}\n\n@book{C, title = {C}}\n".to_string(),
+        )];
+        let err = merge(&sources, DuplicateKeys::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No valid bibliography in file lab.bib - invalid BibTeX entry 'B' at line 3 (byte 23)"
+        );
+    }
+
+    #[test]
+    fn bibtex_entry_spans() {
+        let content = "@book{A, title = {A},\n  note = {Line one\n@book{B, text}}}\n\
+            % TODO: check {braces\n@book{C, title = {C}}\n@book{A, title = {Last A}}\n";
+        let sources = vec![(PathBuf::from("c.bib"), content.to_string())];
+        let mut config = config(DuplicateKeys::Error);
+        config.levels.duplicate_key = Level::Error;
+        let mut diagnostics = Diagnostics::new(config.levels);
+        let bib = super::merge_bibliographies(&sources, &config, &mut diagnostics).unwrap();
+        assert_eq!(bib.len(), 2);
+        assert_eq!(
+            bib.get("A").unwrap().title().unwrap().format_verbatim(),
+            "Last A"
+        );
+        assert_eq!(
+            diagnostics.finish().unwrap_err().to_string(),
+            "1 problem(s) found:\n  duplicate citation key 'A' in c.bib:1 and c.bib:6, using the last one."
+        );
+    }
+
+    #[test]
+    fn parse_name() {
+        let name = super::Name::parse("Knuth, Donald E.");
+        assert_eq!(
+            (name.family.as_str(), name.given.as_str()),
+            ("Knuth", "Donald E.")
+        );
+
+        let name = super::Name::parse("Donald E. Knuth");
+        assert_eq!(
+            (name.family.as_str(), name.given.as_str()),
+            ("Knuth", "Donald E.")
+        );
+
+        let name = super::Name::parse("King, Jr., Martin Luther");
+        assert_eq!(
+            (
+                name.family.as_str(),
+                name.suffix.as_str(),
+                name.given.as_str()
+            ),
+            ("King", "Jr.", "Martin Luther")
+        );
+    }
+}
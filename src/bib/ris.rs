@@ -0,0 +1,252 @@
+//! Conversion of [RIS](https://en.wikipedia.org/wiki/RIS_(file_format)) files,
+//! as exported by most reference managers and library catalogues.
+
+use super::{generate_key, set_field, set_names, Name};
+use biblatex::{Bibliography, Entry, EntryType};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+static TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Z][A-Z0-9])  -(?: (.*))?$").unwrap());
+
+/// Parses RIS records. Returns the bibliography and warnings about unmapped tags.
+pub fn parse(content: &str) -> Result<(Bibliography, Vec<String>), Box<dyn Error>> {
+    let mut bibliography = Bibliography::new();
+    let mut unmapped = BTreeSet::new();
+    let mut record: Option<Vec<(String, String)>> = None;
+
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let (tag, value) = match TAG_REGEX.captures(line) {
+            Some(cap) => (
+                cap[1].to_owned(),
+                cap.get(2).map_or("", |m| m.as_str()).trim().to_owned(),
+            ),
+            None => match record.as_mut().and_then(|r| r.last_mut()) {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                    continue;
+                }
+                None => return Err(format!("unexpected content in line {}", idx + 1).into()),
+            },
+        };
+
+        match (tag.as_str(), record.as_mut()) {
+            ("TY", None) => record = Some(vec![(tag, value)]),
+            ("TY", Some(_)) => {
+                return Err(format!("missing ER before TY in line {}", idx + 1).into());
+            }
+            ("ER", Some(_)) => {
+                let entry = convert_record(&record.take().unwrap(), &bibliography, &mut unmapped);
+                bibliography.insert(entry);
+            }
+            (_, Some(fields)) => fields.push((tag, value)),
+            (_, None) => {
+                return Err(format!("tag {} outside of a record in line {}", tag, idx + 1).into());
+            }
+        }
+    }
+    if record.is_some() {
+        return Err("missing ER at end of file".into());
+    }
+
+    let warnings = unmapped
+        .into_iter()
+        .map(|tag| format!("unmapped RIS tag {}", tag))
+        .collect();
+    Ok((bibliography, warnings))
+}
+
+fn convert_record(
+    record: &[(String, String)],
+    bibliography: &Bibliography,
+    unmapped: &mut BTreeSet<String>,
+) -> Entry {
+    let ris_type = record[0].1.as_str();
+    let entry_type = entry_type(ris_type);
+    let is_article = entry_type == EntryType::Article;
+    let is_book = matches!(entry_type, EntryType::Book | EntryType::InCollection);
+
+    let mut key = None;
+    let mut authors = vec![];
+    let mut editors = vec![];
+    let mut translators = vec![];
+    let mut keywords = vec![];
+    let mut start_page = None;
+    let mut end_page = None;
+    let mut fields: Vec<(&str, String)> = vec![];
+
+    for (tag, value) in &record[1..] {
+        let value = value.clone();
+        match tag.as_str() {
+            "ID" => key = Some(value),
+            "AU" | "A1" => authors.push(parse_name(&value)),
+            "A2" | "ED" => editors.push(parse_name(&value)),
+            "A4" => translators.push(parse_name(&value)),
+            "PY" | "Y1" | "DA" => {
+                if let Some(date) = convert_date(&value) {
+                    fields.push(("date", date));
+                }
+            }
+            "Y2" => {
+                if let Some(date) = convert_date(&value) {
+                    fields.push(("urldate", date));
+                }
+            }
+            "TI" | "T1" | "CT" => fields.push(("title", value)),
+            "BT" => fields.push((if is_book { "title" } else { "booktitle" }, value)),
+            "T2" => fields.push((if is_article { "journal" } else { "booktitle" }, value)),
+            "JO" | "JF" => fields.push(("journal", value)),
+            "JA" | "J2" => fields.push(("shortjournal", value)),
+            "T3" => fields.push(("series", value)),
+            "VL" => fields.push(("volume", value)),
+            "IS" => fields.push(("number", value)),
+            "SP" => start_page = Some(value),
+            "EP" => end_page = Some(value),
+            "ET" => fields.push(("edition", value)),
+            "PB" => fields.push(("publisher", value)),
+            "CY" | "PP" => fields.push(("address", value)),
+            "SN" => fields.push((if is_book { "isbn" } else { "issn" }, value)),
+            "DO" => fields.push(("doi", value)),
+            "UR" | "L2" => fields.push(("url", value)),
+            "AB" | "N2" => fields.push(("abstract", value)),
+            "N1" => fields.push(("note", value)),
+            "LA" => fields.push(("language", value)),
+            "M3" => fields.push(("type", value)),
+            "KW" => keywords.push(value),
+            other => {
+                unmapped.insert(other.to_owned());
+            }
+        }
+    }
+
+    let date = fields
+        .iter()
+        .find(|(field, _)| *field == "date")
+        .map(|(_, date)| date.as_str());
+    let key = key.unwrap_or_else(|| generate_key(&authors, date, bibliography));
+    let mut entry = Entry::new(key, entry_type);
+
+    set_names(&mut entry, "author", &authors);
+    set_names(&mut entry, "editor", &editors);
+    set_names(&mut entry, "translator", &translators);
+    set_field(&mut entry, "keywords", &keywords.join(", "));
+    match (start_page, end_page) {
+        (Some(start), Some(end)) => set_field(&mut entry, "pages", &format!("{}-{}", start, end)),
+        (Some(start), None) => set_field(&mut entry, "pages", &start),
+        _ => {}
+    }
+    for (field, value) in fields {
+        if entry.get(field).is_none() {
+            set_field(&mut entry, field, &value);
+        }
+    }
+
+    entry
+}
+
+/// Parses a name in RIS notation: `Family, Given, Suffix`.
+fn parse_name(name: &str) -> Name {
+    let parts: Vec<_> = name.split(',').map(|p| p.trim().to_owned()).collect();
+    match parts.len() {
+        1 => Name {
+            literal: Some(parts[0].clone()),
+            ..Name::default()
+        },
+        _ => Name {
+            family: parts[0].clone(),
+            given: parts[1].clone(),
+            suffix: parts[2..].join(", "),
+            ..Name::default()
+        },
+    }
+}
+
+/// Converts a RIS date (`YYYY/MM/DD/other`) into ISO 8601.
+fn convert_date(date: &str) -> Option<String> {
+    let mut parts = date.split('/').map(str::trim);
+    let year = parts.next().filter(|y| !y.is_empty())?;
+    let mut result = year.to_owned();
+    if let Some(month) = parts.next().and_then(|m| m.parse::<u8>().ok()) {
+        result.push_str(&format!("-{:02}", month));
+        if let Some(day) = parts.next().and_then(|d| d.parse::<u8>().ok()) {
+            result.push_str(&format!("-{:02}", day));
+        }
+    }
+    Some(result)
+}
+
+fn entry_type(ris_type: &str) -> EntryType {
+    match ris_type {
+        "JOUR" | "JFULL" | "EJOUR" | "MGZN" | "NEWS" | "INPR" => EntryType::Article,
+        "BOOK" | "EBOOK" | "EDBOOK" | "CLSWK" => EntryType::Book,
+        "CHAP" | "ECHAP" => EntryType::InCollection,
+        "CONF" | "CPAPER" => EntryType::InProceedings,
+        "RPRT" => EntryType::Report,
+        "THES" => EntryType::Thesis,
+        "ELEC" | "WEB" | "BLOG" => EntryType::Online,
+        "UNPB" | "MANSCPT" => EntryType::Unpublished,
+        "PAT" => EntryType::Patent,
+        "COMP" => EntryType::Software,
+        "DATA" => EntryType::Dataset,
+        "ENCYC" | "DICT" => EntryType::InReference,
+        _ => EntryType::Misc,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use biblatex::{ChunksExt, EntryType};
+
+    #[test]
+    fn parse_ris() {
+        let (bib, warnings) = super::parse(
+            "TY  - JOUR
+AU  - Knuth, Donald E.
+PY  - 1984/02//
+TI  - Literate Programming
+JO  - The Computer Journal
+VL  - 27
+IS  - 2
+SP  - 97
+EP  - 111
+DO  - 10.1093/comjnl/27.2.97
+C1  - unused
+ER  -
+
+TY  - BOOK
+ID  - Klabnik2018
+AU  - Klabnik, Steve
+AU  - Nichols, Carol
+PY  - 2018
+TI  - The Rust Programming Language
+PB  - No Starch Press
+ER  -
+",
+        )
+        .unwrap();
+
+        assert_eq!(warnings, vec!["unmapped RIS tag C1".to_string()]);
+
+        let entry = bib.get("Knuth1984").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Article);
+        assert_eq!(
+            entry.journal().unwrap().format_verbatim(),
+            "The Computer Journal"
+        );
+        assert_eq!(entry.volume(), Some(27));
+        assert_eq!(entry.get("pages").unwrap().format_verbatim(), "97-111");
+        assert_eq!(entry.get("date").unwrap().format_verbatim(), "1984-02");
+        assert_eq!(entry.author().unwrap()[0].given_name, "Donald E.");
+
+        let entry = bib.get("Klabnik2018").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Book);
+        assert_eq!(entry.author().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,532 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::ops::Range;
+
+const KEY_PATTERN: &str = r##"^(-)?@([^\[\]\s\.,;"#'()={}%]+)"##;
+static KEY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(KEY_PATTERN).unwrap());
+
+const LOCATOR_VALUE: &str = r"(?:\d+\w*|[ivxlcdmIVXLCDM]+\b)";
+static LOCATOR_VALUES_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"^{v}(?:\s*[-–—,&]\s*{v})*", v = LOCATOR_VALUE)).unwrap());
+static LOCATOR_LABEL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([\p{L}§¶]+)\.?\s*").unwrap());
+
+static AUTOLINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^<(?:[A-Za-z][A-Za-z0-9+.\-]{1,31}:[^\s<>]*|[^\s<>@]+@[^\s<>]+)>").unwrap()
+});
+static HTML_TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^(?:<!--.*?-->|</?([A-Za-z][A-Za-z0-9\-]*)(?:\s(?:[^>"']|"[^"]*"|'[^']*')*)?/?>)"#,
+    )
+    .unwrap()
+});
+static URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:[A-Za-z][A-Za-z0-9+.\-]*://|www\.)[^\s<>]*").unwrap());
+static LINK_DEFINITION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^ {0,3}\[[^\]]+\]:\s*\S").unwrap());
+
+/// HTML elements with literal content, where citations are not detected.
+const LITERAL_ELEMENTS: &[&str] = &["code", "pre", "kbd", "samp", "script", "style"];
+
+/// A citation, either a bare `@key` or a bracketed group like `[see @key, p. 3; @other]`.
+#[derive(Debug, PartialEq)]
+pub struct Citation {
+    pub items: Vec<CitationItem>,
+    pub bracketed: bool,
+}
+
+/// A single cited key, with optional prefix, locator and suffix.
+#[derive(Debug, PartialEq)]
+pub struct CitationItem {
+    pub key: String,
+    pub no_author: bool,
+    pub prefix: String,
+    pub locator: Option<Locator>,
+    pub suffix: String,
+}
+
+impl CitationItem {
+    /// The item's key as written in the source, e.g. `-@Doe2020`.
+    pub fn original(&self) -> String {
+        format!("{}@{}", if self.no_author { "-" } else { "" }, self.key)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Locator {
+    pub label: LocatorLabel,
+    pub value: String,
+}
+
+impl Locator {
+    pub fn is_plural(&self) -> bool {
+        self.value.contains(&['-', '–', '—', ',', '&'][..])
+    }
+}
+
+/// Position of a cite relative to earlier cites of the same key in the document,
+/// as tested by CSL styles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CitePosition {
+    First,
+    Subsequent,
+    /// The only key of the previous citation, with the same locator
+    Ibid,
+    /// The only key of the previous citation, with a different locator
+    IbidWithLocator,
+}
+
+impl CitePosition {
+    /// Whether the position passes a CSL `position` test. Ibid positions are also subsequent.
+    pub fn matches(self, test: &str) -> bool {
+        match test {
+            "first" => self == CitePosition::First,
+            "subsequent" => self != CitePosition::First,
+            "ibid" => matches!(self, CitePosition::Ibid | CitePosition::IbidWithLocator),
+            "ibid-with-locator" => self == CitePosition::IbidWithLocator,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LocatorLabel {
+    Book,
+    Chapter,
+    Column,
+    Figure,
+    Folio,
+    Line,
+    Note,
+    Number,
+    Opus,
+    Page,
+    Paragraph,
+    Part,
+    Section,
+    Verse,
+    Volume,
+}
+
+impl LocatorLabel {
+    fn parse(label: &str) -> Option<Self> {
+        let label = label.to_lowercase();
+        Some(match label.as_str() {
+            "book" | "books" | "bk" | "bks" => LocatorLabel::Book,
+            "chapter" | "chapters" | "chap" | "chaps" | "ch" | "chs" => LocatorLabel::Chapter,
+            "column" | "columns" | "col" | "cols" => LocatorLabel::Column,
+            "figure" | "figures" | "fig" | "figs" => LocatorLabel::Figure,
+            "folio" | "folios" | "fol" | "fols" => LocatorLabel::Folio,
+            "line" | "lines" | "l" | "ll" => LocatorLabel::Line,
+            "note" | "notes" | "n" | "nn" => LocatorLabel::Note,
+            "number" | "numbers" | "no" | "nos" => LocatorLabel::Number,
+            "opus" | "opera" | "op" | "opp" => LocatorLabel::Opus,
+            "page" | "pages" | "p" | "pp" => LocatorLabel::Page,
+            "paragraph" | "paragraphs" | "para" | "paras" | "¶" | "¶¶" => {
+                LocatorLabel::Paragraph
+            }
+            "part" | "parts" | "pt" | "pts" => LocatorLabel::Part,
+            "section" | "sections" | "sec" | "secs" | "§" | "§§" => LocatorLabel::Section,
+            "verse" | "verses" | "v" | "vv" => LocatorLabel::Verse,
+            "volume" | "volumes" | "vol" | "vols" => LocatorLabel::Volume,
+            _ => return None,
+        })
+    }
+
+    /// The locator's CSL term name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LocatorLabel::Book => "book",
+            LocatorLabel::Chapter => "chapter",
+            LocatorLabel::Column => "column",
+            LocatorLabel::Figure => "figure",
+            LocatorLabel::Folio => "folio",
+            LocatorLabel::Line => "line",
+            LocatorLabel::Note => "note",
+            LocatorLabel::Number => "number",
+            LocatorLabel::Opus => "opus",
+            LocatorLabel::Page => "page",
+            LocatorLabel::Paragraph => "paragraph",
+            LocatorLabel::Part => "part",
+            LocatorLabel::Section => "section",
+            LocatorLabel::Verse => "verse",
+            LocatorLabel::Volume => "volume",
+        }
+    }
+
+    /// The abbreviated term, in singular or plural form.
+    pub fn term(&self, plural: bool) -> &'static str {
+        let (singular, multiple) = match self {
+            LocatorLabel::Book => ("bk.", "bks."),
+            LocatorLabel::Chapter => ("chap.", "chaps."),
+            LocatorLabel::Column => ("col.", "cols."),
+            LocatorLabel::Figure => ("fig.", "figs."),
+            LocatorLabel::Folio => ("fol.", "fols."),
+            LocatorLabel::Line => ("l.", "ll."),
+            LocatorLabel::Note => ("n.", "nn."),
+            LocatorLabel::Number => ("no.", "nos."),
+            LocatorLabel::Opus => ("op.", "opp."),
+            LocatorLabel::Page => ("p.", "pp."),
+            LocatorLabel::Paragraph => ("para.", "paras."),
+            LocatorLabel::Part => ("pt.", "pts."),
+            LocatorLabel::Section => ("sec.", "secs."),
+            LocatorLabel::Verse => ("v.", "vv."),
+            LocatorLabel::Volume => ("vol.", "vols."),
+        };
+        if plural {
+            multiple
+        } else {
+            singular
+        }
+    }
+}
+
+/// Finds all citations in a line of text, with their byte ranges.
+///
+/// Skips escaped characters, code spans, autolinks, URLs, link destinations and definitions,
+/// and HTML tags. A bare `@` only starts a citation if not preceded by a word character,
+/// so e-mail addresses are not taken as citations.
+pub fn parse_citations(line: &str) -> Vec<(Range<usize>, Citation)> {
+    let mut result = vec![];
+    let mut pos = 0;
+
+    if LINK_DEFINITION_REGEX.is_match(line) {
+        return result;
+    }
+
+    while pos < line.len() {
+        let rest = &line[pos..];
+        if let Some(len) = skip_len(rest, &line[..pos]) {
+            pos += len;
+            continue;
+        }
+        if rest.starts_with('[') {
+            if let Some((len, citation)) = parse_group(rest) {
+                result.push((pos..pos + len, citation));
+                pos += len;
+                continue;
+            }
+        } else if let Some(caps) = KEY_REGEX
+            .captures(rest)
+            .filter(|_| can_start_key(&line[..pos]))
+        {
+            let len = caps.get(0).unwrap().end();
+            result.push((
+                pos..pos + len,
+                Citation {
+                    items: vec![CitationItem {
+                        key: caps[2].to_owned(),
+                        no_author: caps.get(1).is_some(),
+                        prefix: String::new(),
+                        locator: None,
+                        suffix: String::new(),
+                    }],
+                    bracketed: false,
+                },
+            ));
+            pos += len;
+            continue;
+        }
+        pos += rest.chars().next().unwrap().len_utf8();
+    }
+
+    result
+}
+
+/// Returns the length of a Markdown or HTML construct at the start of `rest`
+/// that can't contain citations, or `None`.
+fn skip_len(rest: &str, before: &str) -> Option<usize> {
+    match rest.chars().next()? {
+        '\\' => Some(1 + rest[1..].chars().next().map_or(0, char::len_utf8)),
+        '`' => {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            Some(code_span_len(rest, ticks).unwrap_or(ticks))
+        }
+        '<' => {
+            if let Some(m) = AUTOLINK_REGEX.find(rest) {
+                return Some(m.end());
+            }
+            let caps = HTML_TAG_REGEX.captures(rest)?;
+            let tag_end = caps.get(0).unwrap().end();
+            let literal = caps
+                .get(1)
+                .map(|name| name.as_str().to_lowercase())
+                .filter(|name| LITERAL_ELEMENTS.contains(&name.as_str()))
+                .filter(|_| !rest.starts_with("</") && !rest[..tag_end].ends_with("/>"));
+            match literal {
+                Some(name) => {
+                    let closing = format!("</{}", name);
+                    let end = rest[tag_end..]
+                        .to_lowercase()
+                        .find(&closing)
+                        .map_or(rest.len(), |idx| tag_end + idx + closing.len());
+                    Some(end)
+                }
+                None => Some(tag_end),
+            }
+        }
+        ']' if rest[1..].starts_with('(') => Some(link_destination_len(rest)),
+        _ if !before.ends_with(|c: char| c.is_alphanumeric()) => {
+            URL_REGEX.find(rest).map(|m| m.end())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the length of a code span opened by `ticks` backticks, if it is closed on this line.
+fn code_span_len(text: &str, ticks: usize) -> Option<usize> {
+    let mut pos = ticks;
+    while let Some(start) = text[pos..].find('`') {
+        let start = pos + start;
+        let len = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if len == ticks {
+            return Some(start + len);
+        }
+        pos = start + len;
+    }
+    None
+}
+
+/// Returns the length of `](destination "title")`, balancing nested parentheses.
+fn link_destination_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut chars = text.char_indices().skip(1);
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return idx + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    1
+}
+
+/// Whether a key may start after the given text, i.e. it does not end with a word character.
+fn can_start_key(before: &str) -> bool {
+    !before.ends_with(|c: char| c.is_alphanumeric() || "._%+-/:=@&?~".contains(c))
+}
+
+/// Parses a bracketed citation group at the start of `text`.
+/// Returns the group's length in bytes, or `None` if it is not a citation group.
+fn parse_group(text: &str) -> Option<(usize, Citation)> {
+    let close = text[1..].find(&['[', ']'][..])? + 1;
+    if &text[close..=close] != "]" {
+        return None;
+    }
+    // Markdown links and link references.
+    if text[close + 1..].starts_with('(') || text[close + 1..].starts_with('[') {
+        return None;
+    }
+
+    let items = text[1..close]
+        .split(';')
+        .map(parse_item)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((
+        close + 1,
+        Citation {
+            items,
+            bracketed: true,
+        },
+    ))
+}
+
+fn parse_item(text: &str) -> Option<CitationItem> {
+    let start = text
+        .match_indices('@')
+        .map(|(idx, _)| {
+            if text[..idx].ends_with('-') {
+                idx - 1
+            } else {
+                idx
+            }
+        })
+        .find(|idx| can_start_key(&text[..*idx]) && !text[..*idx].ends_with('\\'))?;
+    let caps = KEY_REGEX.captures(&text[start..])?;
+    let rest = &text[start + caps.get(0).unwrap().end()..];
+
+    let (locator, suffix) = match rest.strip_prefix(',') {
+        Some(after) => match parse_locator(after.trim_start()) {
+            Some((locator, len)) => (Some(locator), &after.trim_start()[len..]),
+            None => (None, rest),
+        },
+        None => (None, rest),
+    };
+
+    Some(CitationItem {
+        key: caps[2].to_owned(),
+        no_author: caps.get(1).is_some(),
+        prefix: text[..start].trim().to_owned(),
+        locator,
+        suffix: suffix.trim_end().to_owned(),
+    })
+}
+
+/// Parses a locator like `p. 3`, `ll. 3-5` or a bare page number like `xii`.
+/// Returns the locator and its length in bytes.
+fn parse_locator(text: &str) -> Option<(Locator, usize)> {
+    // A bare value is a page, unless it is a label like `v` or `ll` followed by a value
+    if let Some(value) = LOCATOR_VALUES_REGEX.find(text) {
+        if !LOCATOR_VALUES_REGEX.is_match(text[value.end()..].trim_start()) {
+            let locator = Locator {
+                label: LocatorLabel::Page,
+                value: value.as_str().to_owned(),
+            };
+            return Some((locator, value.end()));
+        }
+    }
+
+    let caps = LOCATOR_LABEL_REGEX.captures(text)?;
+    let label = LocatorLabel::parse(&caps[1])?;
+    let start = caps.get(0).unwrap().end();
+    let value = LOCATOR_VALUES_REGEX.find(&text[start..])?;
+    Some((
+        Locator {
+            label,
+            value: value.as_str().to_owned(),
+        },
+        start + value.end(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_citations, LocatorLabel};
+
+    #[test]
+    fn parse_bare_citations() {
+        let citations = parse_citations("See @Knuth1984 and -@Baumer2015.");
+
+        assert_eq!(citations.len(), 2);
+        assert_eq!(citations[0].0, 4..14);
+        assert_eq!(citations[0].1.items[0].key, "Knuth1984");
+        assert!(!citations[0].1.bracketed);
+        assert!(citations[1].1.items[0].no_author);
+        assert_eq!(citations[1].1.items[0].key, "Baumer2015");
+    }
+
+    #[test]
+    fn parse_citation_group() {
+        let line = "Text [see @Knuth1984, pp. 33-35; also -@Baumer2015, ch. 2 and passim].";
+        let citations = parse_citations(line);
+
+        assert_eq!(citations.len(), 1);
+        let (range, citation) = &citations[0];
+        assert_eq!(&line[range.clone()], &line[5..line.len() - 1]);
+        assert!(citation.bracketed);
+        assert_eq!(citation.items.len(), 2);
+
+        let first = &citation.items[0];
+        assert_eq!(first.prefix, "see");
+        assert_eq!(first.key, "Knuth1984");
+        let locator = first.locator.as_ref().unwrap();
+        assert_eq!(locator.label, LocatorLabel::Page);
+        assert_eq!(locator.value, "33-35");
+        assert!(locator.is_plural());
+        assert_eq!(first.suffix, "");
+
+        let second = &citation.items[1];
+        assert_eq!(second.prefix, "also");
+        assert!(second.no_author);
+        let locator = second.locator.as_ref().unwrap();
+        assert_eq!(locator.label, LocatorLabel::Chapter);
+        assert_eq!(locator.value, "2");
+        assert_eq!(second.suffix, " and passim");
+    }
+
+    #[test]
+    fn parse_ignores_links() {
+        let citations = parse_citations("A [link](https://example.com), [ref][1] and [[_REFS_]]");
+        assert!(citations.is_empty());
+    }
+
+    #[test]
+    fn parse_ignores_code_urls_and_emails() {
+        let line = "Mail user@example.org or <user@example.org>, use `@decorator`, \\@escaped, \
+                    see https://twitter.com/@handle, <a href=\"https://x.org/@me\">x</a>, \
+                    [tweet](https://x.org/@other), <code>@code</code> and [@Knuth1984] by @Baumer2015";
+        let citations = parse_citations(line);
+
+        let keys: Vec<_> = citations
+            .iter()
+            .flat_map(|(_, cit)| cit.items.iter().map(|item| item.key.as_str()))
+            .collect();
+        assert_eq!(keys, vec!["Knuth1984", "Baumer2015"]);
+    }
+
+    #[test]
+    fn parse_ignores_link_definitions() {
+        assert!(parse_citations("[handle]: https://x.org/@handle").is_empty());
+        assert!(parse_citations("[mail me at user@example.org]").is_empty());
+        assert_eq!(
+            parse_citations("``code with ` and @key`` @Knuth1984").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn parse_locators() {
+        let locator = |text: &str| {
+            let citations = parse_citations(text);
+            let item = &citations[0].1.items[0];
+            item.locator
+                .as_ref()
+                .map(|locator| (locator.label, locator.value.clone(), item.suffix.clone()))
+        };
+        let page = |value: &str| Some((LocatorLabel::Page, value.to_string(), String::new()));
+        assert_eq!(locator("[@A, vi]"), page("vi"));
+        assert_eq!(locator("[@A, xii]"), page("xii"));
+        assert_eq!(locator("[@A, 33-35]"), page("33-35"));
+        assert_eq!(
+            locator("[@A, v 3]"),
+            Some((LocatorLabel::Verse, "3".to_string(), String::new()))
+        );
+        assert_eq!(
+            locator("[@A, p3]"),
+            Some((LocatorLabel::Page, "3".to_string(), String::new()))
+        );
+        assert_eq!(locator("[@A, chx 3]"), None);
+
+        let plurals = [
+            ("books", LocatorLabel::Book),
+            ("chapters", LocatorLabel::Chapter),
+            ("columns", LocatorLabel::Column),
+            ("figures", LocatorLabel::Figure),
+            ("folios", LocatorLabel::Folio),
+            ("lines", LocatorLabel::Line),
+            ("notes", LocatorLabel::Note),
+            ("numbers", LocatorLabel::Number),
+            ("opera", LocatorLabel::Opus),
+            ("pages", LocatorLabel::Page),
+            ("paragraphs", LocatorLabel::Paragraph),
+            ("parts", LocatorLabel::Part),
+            ("sections", LocatorLabel::Section),
+            ("verses", LocatorLabel::Verse),
+            ("volumes", LocatorLabel::Volume),
+        ];
+        for (plural, label) in plurals.iter() {
+            assert_eq!(
+                locator(&format!("[@B, {} 3]", plural)),
+                Some((*label, "3".to_string(), String::new())),
+                "{}",
+                plural
+            );
+        }
+    }
+
+    #[test]
+    fn parse_suffix_without_locator() {
+        let citations = parse_citations("[@Knuth1984, an early work]");
+        let item = &citations[0].1.items[0];
+        assert!(item.locator.is_none());
+        assert_eq!(item.suffix, ", an early work");
+    }
+}
@@ -1,11 +1,16 @@
+use crate::csl::{self, Style};
 use std::convert::TryFrom;
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 #[derive(PartialEq)]
 pub enum CitationStyle {
     Index,
     AuthorYear,
+    /// Footnotes with the full reference on first citation, and a short form later on
+    Notes,
+    Csl(Box<Style>),
 }
 
 impl FromStr for CitationStyle {
@@ -15,8 +20,12 @@ impl FromStr for CitationStyle {
         match s {
             "numbered" => Ok(CitationStyle::Index),
             "author-year" => Ok(CitationStyle::AuthorYear),
+            "notes" => Ok(CitationStyle::Notes),
+            file if file.ends_with(".csl") => {
+                Ok(CitationStyle::Csl(Box::new(csl::load_style(file)?)))
+            }
             other => Err(format!(
-                "Unknown citation style '{}'. Use 'numbered' or 'author-year'",
+                "Unknown citation style '{}'. Use 'numbered', 'author-year', 'notes' or a .csl file",
                 other
             )
             .into()),
@@ -24,13 +33,601 @@ impl FromStr for CitationStyle {
     }
 }
 
+#[derive(PartialEq, Debug)]
+pub enum DuplicateKeys {
+    FirstWins,
+    LastWins,
+    Error,
+}
+
+impl FromStr for DuplicateKeys {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-wins" => Ok(DuplicateKeys::FirstWins),
+            "last-wins" => Ok(DuplicateKeys::LastWins),
+            "error" => Ok(DuplicateKeys::Error),
+            other => Err(format!(
+                "Unknown duplicate keys policy '{}'. Use 'first-wins', 'last-wins' or 'error'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum BibFormat {
+    BibTeX,
+    CslJson,
+    Hayagriva,
+    Ris,
+    EndNoteXml,
+}
+
+impl BibFormat {
+    /// Detects the format from a file's extension, defaulting to BibTeX.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .as_deref()
+        {
+            Some("json") => BibFormat::CslJson,
+            Some("yaml") | Some("yml") => BibFormat::Hayagriva,
+            Some("ris") => BibFormat::Ris,
+            Some("xml") => BibFormat::EndNoteXml,
+            _ => BibFormat::BibTeX,
+        }
+    }
+}
+
+impl FromStr for BibFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bibtex" | "biblatex" => Ok(BibFormat::BibTeX),
+            "csl-json" => Ok(BibFormat::CslJson),
+            "hayagriva" => Ok(BibFormat::Hayagriva),
+            "ris" => Ok(BibFormat::Ris),
+            "endnote-xml" => Ok(BibFormat::EndNoteXml),
+            other => Err(format!(
+                "Unknown bibliography format '{}'. Use 'bibtex', 'csl-json', 'hayagriva', 'ris' or 'endnote-xml'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// Which date parts to show in the reference lists of built-in styles.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DateFormat {
+    Year,
+    YearMonth,
+    Full,
+}
+
+impl FromStr for DateFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "year" => Ok(DateFormat::Year),
+            "year-month" => Ok(DateFormat::YearMonth),
+            "full" => Ok(DateFormat::Full),
+            other => Err(format!(
+                "Unknown date format '{}'. Use 'year', 'year-month' or 'full'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// Whether and how references link back to the places they were cited.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Backlinks {
+    None,
+    /// Links labelled 1, 2, 3, ...
+    Numbers,
+    /// Links labelled with the heading of the section containing the citation
+    Sections,
+}
+
+impl FromStr for Backlinks {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Backlinks::None),
+            "numbers" => Ok(Backlinks::Numbers),
+            "sections" => Ok(Backlinks::Sections),
+            other => Err(format!(
+                "Unknown backlinks option '{}'. Use 'none', 'numbers' or 'sections'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// Whether and how citations show the full reference when hovered.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Tooltips {
+    None,
+    /// A Markdown link title
+    Title,
+    /// An HTML `<span>` with a `title` attribute
+    Html,
+}
+
+impl FromStr for Tooltips {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Tooltips::None),
+            "title" => Ok(Tooltips::Title),
+            "html" => Ok(Tooltips::Html),
+            other => Err(format!(
+                "Unknown tooltips option '{}'. Use 'none', 'title' or 'html'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// What to sort reference lists by.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SortKey {
+    /// Order of first citation
+    Citation,
+    Author,
+    /// Authors, then year, then title
+    AuthorYearTitle,
+    Year,
+    Title,
+    /// Entry type, then authors, year and title
+    Type,
+    /// Order in the bibliography file
+    None,
+}
+
+/// Sort order of reference lists, like `year` or `year-desc`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl FromStr for Sort {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, descending) = match s.strip_suffix("-desc") {
+            Some(key) => (key, true),
+            None => (s, false),
+        };
+        let key = match key {
+            "citation" | "citation-order" => SortKey::Citation,
+            "author" => SortKey::Author,
+            "author-year-title" => SortKey::AuthorYearTitle,
+            "year" => SortKey::Year,
+            "title" => SortKey::Title,
+            "type" => SortKey::Type,
+            "none" => SortKey::None,
+            _ => {
+                return Err(format!(
+                    "Unknown sort order '{}'. Use 'citation-order', 'author', 'author-year-title', 'year', 'title', 'type' or 'none', optionally followed by '-desc'",
+                    s
+                )
+                .into())
+            }
+        };
+        Ok(Sort { key, descending })
+    }
+}
+
+/// Texts used by the built-in styles.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Terms {
+    pub no_date: String,
+    pub forthcoming: String,
+    pub in_press: String,
+    pub et_al: String,
+    pub ibid: String,
+}
+
+impl Terms {
+    fn from_config(value: Option<&toml::Value>) -> Result<Self, Box<dyn Error>> {
+        if let Some(table) = value.and_then(|v| v.as_table()) {
+            for key in table.keys() {
+                if !TERM_KEYS.contains(&key.as_str()) {
+                    return Err(format!(
+                        "Unknown term '{}' in option 'terms'. Use one of {}",
+                        key,
+                        TERM_KEYS.join(", ")
+                    )
+                    .into());
+                }
+            }
+        }
+        let term = |key: &str, default: &str| {
+            value
+                .and_then(|v| v.get(key))
+                .and_then(|s| s.as_str())
+                .unwrap_or(default)
+                .to_owned()
+        };
+        Ok(Terms {
+            no_date: term("no-date", "n.d."),
+            forthcoming: term("forthcoming", "forthcoming"),
+            in_press: term("in-press", "in press"),
+            et_al: term("et-al", "et al."),
+            ibid: term("ibid", "Ibid."),
+        })
+    }
+}
+
+const TERM_KEYS: &[&str] = &["no-date", "forthcoming", "in-press", "et-al", "ibid"];
+
+/// Order of family and given names in reference lists of built-in styles.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum NameOrder {
+    FamilyFirst,
+    GivenFirst,
+}
+
+impl FromStr for NameOrder {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "family-first" => Ok(NameOrder::FamilyFirst),
+            "given-first" => Ok(NameOrder::GivenFirst),
+            other => Err(format!(
+                "Unknown name order '{}'. Use 'family-first' or 'given-first'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// Formatting of author names in built-in styles.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Names {
+    pub order: NameOrder,
+    /// Abbreviate given names to initials
+    pub initials: bool,
+    /// Add a period after each initial
+    pub initials_period: bool,
+    /// Keep hyphens of given names in initials, like `J-P` for Jean-Paul
+    pub initials_hyphen: bool,
+    /// Show name prefixes (von, van der) in front of the family name, also in citations
+    pub use_prefix: bool,
+    /// Sort names under their prefix, like "de Gaulle" under D rather than G
+    pub sort_prefix: bool,
+    /// Separator between names
+    pub separator: String,
+    /// Conjunction before the last name in reference lists, if any
+    pub and: Option<String>,
+    /// Conjunction before the last name in citations
+    pub citation_and: String,
+    /// Number of names from which citations are truncated with "et al."
+    pub et_al_min: usize,
+    /// Number of names shown in truncated citations
+    pub et_al_use_first: usize,
+    /// Number of names from which reference lists are truncated, if any
+    pub refs_et_al_min: Option<usize>,
+    /// Number of names shown in truncated reference lists
+    pub refs_et_al_use_first: usize,
+}
+
+impl Names {
+    fn from_config(value: Option<&toml::Value>) -> Result<Self, Box<dyn Error>> {
+        if let Some(table) = value.and_then(|v| v.as_table()) {
+            for key in table.keys() {
+                if !NAME_KEYS.contains(&key.as_str()) {
+                    return Err(format!(
+                        "Unknown key '{}' in option 'names'. Use one of {}",
+                        key,
+                        NAME_KEYS.join(", ")
+                    )
+                    .into());
+                }
+            }
+        }
+        let get = |key: &str| value.and_then(|v| v.get(key));
+        let flag = |key: &str, default: bool| get(key).and_then(|v| v.as_bool()).unwrap_or(default);
+        let text = |key: &str| get(key).and_then(|v| v.as_str()).map(|s| s.to_owned());
+        let number = |key: &str| -> Result<Option<usize>, Box<dyn Error>> {
+            match get(key) {
+                None => Ok(None),
+                Some(v) => match v.as_integer() {
+                    Some(n) if n > 0 => Ok(Some(n as usize)),
+                    _ => Err(
+                        format!("Option '{}' in 'names' must be a positive integer", key).into(),
+                    ),
+                },
+            }
+        };
+        Ok(Names {
+            order: text("order")
+                .as_deref()
+                .map(NameOrder::from_str)
+                .unwrap_or(Ok(NameOrder::FamilyFirst))?,
+            initials: flag("initials", true),
+            initials_period: flag("initials-period", false),
+            initials_hyphen: flag("initials-hyphen", true),
+            use_prefix: flag("use-prefix", false),
+            sort_prefix: flag("sort-prefix", false),
+            separator: text("separator").unwrap_or_else(|| ", ".to_owned()),
+            and: text("and"),
+            citation_and: text("citation-and").unwrap_or_else(|| "&".to_owned()),
+            et_al_min: number("et-al-min")?.unwrap_or(3),
+            et_al_use_first: number("et-al-use-first")?.unwrap_or(1),
+            refs_et_al_min: number("refs-et-al-min")?,
+            refs_et_al_use_first: number("refs-et-al-use-first")?.unwrap_or(1),
+        })
+    }
+}
+
+const NAME_KEYS: &[&str] = &[
+    "order",
+    "initials",
+    "initials-period",
+    "initials-hyphen",
+    "use-prefix",
+    "sort-prefix",
+    "separator",
+    "and",
+    "citation-and",
+    "et-al-min",
+    "et-al-use-first",
+    "refs-et-al-min",
+    "refs-et-al-use-first",
+];
+
+/// Identifiers appended to references of built-in styles, and whether to link the title.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Links {
+    pub doi: bool,
+    pub arxiv: bool,
+    pub isbn: bool,
+    pub issn: bool,
+    pub url: bool,
+    /// Link the title to the DOI, or else to the URL
+    pub title: bool,
+}
+
+impl Links {
+    fn from_config(value: Option<&toml::Value>) -> Result<Self, Box<dyn Error>> {
+        if let Some(table) = value.and_then(|v| v.as_table()) {
+            for key in table.keys() {
+                if !LINK_KEYS.contains(&key.as_str()) {
+                    return Err(format!(
+                        "Unknown key '{}' in option 'links'. Use one of {}",
+                        key,
+                        LINK_KEYS.join(", ")
+                    )
+                    .into());
+                }
+            }
+        }
+        let flag = |key: &str| {
+            value
+                .and_then(|v| v.get(key))
+                .and_then(|s| s.as_bool())
+                .unwrap_or(false)
+        };
+        Ok(Links {
+            doi: flag("doi"),
+            arxiv: flag("arxiv"),
+            isbn: flag("isbn"),
+            issn: flag("issn"),
+            url: flag("url"),
+            title: flag("title"),
+        })
+    }
+}
+
+const LINK_KEYS: &[&str] = &["doi", "arxiv", "isbn", "issn", "url", "title"];
+
+/// Brackets around citations in the numbered style.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Brackets {
+    Square,
+    Round,
+    Superscript,
+}
+
+impl Brackets {
+    /// Encloses the numbers of a citation in the brackets.
+    pub fn wrap(&self, text: &str) -> String {
+        match self {
+            Brackets::Square => format!("[{}]", text),
+            Brackets::Round => format!("({})", text),
+            Brackets::Superscript => format!("<sup>{}</sup>", text),
+        }
+    }
+}
+
+impl FromStr for Brackets {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "square" => Ok(Brackets::Square),
+            "round" => Ok(Brackets::Round),
+            "superscript" => Ok(Brackets::Superscript),
+            other => Err(format!(
+                "Unknown brackets '{}'. Use 'square', 'round' or 'superscript'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// Formatting of citations in the numbered style.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Numbers {
+    pub brackets: Brackets,
+    /// Merge adjacent citations, and compress consecutive numbers to ranges like `1–3`
+    pub compress: bool,
+    /// Separator between compressed numbers and ranges
+    pub delimiter: String,
+    /// Separator between citations that are not compressed, like ones with a locator
+    pub citation_delimiter: String,
+    /// Separator between the first and last number of a range
+    pub range_delimiter: String,
+    /// Number references by their position in the sorted list, rather than by first citation
+    pub sorted: bool,
+}
+
+impl Numbers {
+    fn from_config(value: Option<&toml::Value>) -> Result<Self, Box<dyn Error>> {
+        if let Some(table) = value.and_then(|v| v.as_table()) {
+            for key in table.keys() {
+                if !NUMBER_KEYS.contains(&key.as_str()) {
+                    return Err(format!(
+                        "Unknown key '{}' in option 'numbers'. Use one of {}",
+                        key,
+                        NUMBER_KEYS.join(", ")
+                    )
+                    .into());
+                }
+            }
+        }
+        let get = |key: &str| value.and_then(|v| v.get(key));
+        let text = |key: &str| get(key).and_then(|v| v.as_str()).map(|s| s.to_owned());
+        Ok(Numbers {
+            brackets: text("brackets")
+                .as_deref()
+                .map(Brackets::from_str)
+                .unwrap_or(Ok(Brackets::Square))?,
+            compress: get("compress").and_then(|v| v.as_bool()).unwrap_or(true),
+            delimiter: text("delimiter").unwrap_or_else(|| ", ".to_owned()),
+            citation_delimiter: text("citation-delimiter").unwrap_or_else(|| "; ".to_owned()),
+            range_delimiter: text("range-delimiter").unwrap_or_else(|| "–".to_owned()),
+            sorted: get("sorted").and_then(|v| v.as_bool()).unwrap_or(false),
+        })
+    }
+}
+
+const NUMBER_KEYS: &[&str] = &[
+    "brackets",
+    "citation-delimiter",
+    "compress",
+    "delimiter",
+    "range-delimiter",
+    "sorted",
+];
+
+/// How to handle a category of problems.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Level {
+    Ignore,
+    Warn,
+    Error,
+}
+
+impl FromStr for Level {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(Level::Ignore),
+            "warn" => Ok(Level::Warn),
+            "error" => Ok(Level::Error),
+            other => {
+                Err(format!("Unknown level '{}'. Use 'ignore', 'warn' or 'error'", other).into())
+            }
+        }
+    }
+}
+
+/// Levels for the different categories of problems.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Levels {
+    pub unknown_key: Level,
+    pub missing_placeholder: Level,
+    pub duplicate_key: Level,
+    pub unsupported_type: Level,
+    pub missing_file: Level,
+    pub ignored_content: Level,
+}
+
+impl Levels {
+    fn from_config(value: Option<&toml::Value>, strict: bool) -> Result<Self, Box<dyn Error>> {
+        let default = if strict { Level::Error } else { Level::Warn };
+        let level = |key: &str| {
+            value
+                .and_then(|v| v.get(key))
+                .and_then(|s| s.as_str())
+                .map(Level::from_str)
+                .unwrap_or(Ok(default))
+        };
+        if let Some(table) = value.and_then(|v| v.as_table()) {
+            for key in table.keys() {
+                if !LEVEL_KEYS.contains(&key.as_str()) {
+                    return Err(format!(
+                        "Unknown problem category '{}' in option 'levels'. Use one of {}",
+                        key,
+                        LEVEL_KEYS.join(", ")
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(Levels {
+            unknown_key: level("unknown-key")?,
+            missing_placeholder: level("missing-placeholder")?,
+            duplicate_key: level("duplicate-key")?,
+            unsupported_type: level("unsupported-type")?,
+            missing_file: level("missing-file")?,
+            ignored_content: level("ignored-content")?,
+        })
+    }
+}
+
+const LEVEL_KEYS: &[&str] = &[
+    "unknown-key",
+    "missing-placeholder",
+    "duplicate-key",
+    "unsupported-type",
+    "missing-file",
+    "ignored-content",
+];
+
 pub struct Config {
-    pub bib_file: String,
+    pub bib_files: Vec<String>,
+    pub bib_format: Option<BibFormat>,
+    pub duplicate_keys: DuplicateKeys,
     pub citation_style: CitationStyle,
     pub refs_file: Option<String>,
+    pub document_order: Vec<PathBuf>,
     pub placeholder: String,
+    pub local_placeholder: String,
+    /// Order of reference lists, if not the style's order
+    pub sort: Option<Sort>,
     pub render_key: bool,
     pub link_refs: bool,
+    pub backlinks: Backlinks,
+    pub tooltips: Tooltips,
+    pub disambiguate_names: bool,
+    pub date_format: DateFormat,
+    pub terms: Terms,
+    pub names: Names,
+    pub links: Links,
+    pub numbers: Numbers,
+    pub levels: Levels,
 }
 
 impl TryFrom<&toml::Value> for Config {
@@ -38,25 +635,47 @@ impl TryFrom<&toml::Value> for Config {
 
     fn try_from(value: &toml::Value) -> Result<Self, Self::Error> {
         Ok(Self {
-            bib_file: value
-                .get("bibliography")
+            bib_files: string_list(value, "bibliography")?
+                .unwrap_or_else(|| vec!["bibliography.bib".to_owned()]),
+            bib_format: value
+                .get("format")
                 .and_then(|s| s.as_str())
-                .unwrap_or("bibliography.bib")
-                .to_owned(),
+                .map(BibFormat::from_str)
+                .transpose()?,
+            duplicate_keys: value
+                .get("duplicate-keys")
+                .and_then(|s| s.as_str())
+                .map(DuplicateKeys::from_str)
+                .unwrap_or(Ok(DuplicateKeys::FirstWins))?,
             citation_style: value
                 .get("style")
                 .and_then(|s| s.as_str())
-                .map(|s| CitationStyle::from_str(s))
+                .map(CitationStyle::from_str)
                 .unwrap_or(Ok(CitationStyle::AuthorYear))?,
             refs_file: value
                 .get("refs-file")
                 .and_then(|s| s.as_str())
                 .map(|s| s.to_string()),
+            document_order: string_list(value, "document-order")?
+                .unwrap_or_default()
+                .iter()
+                .map(PathBuf::from)
+                .collect(),
             placeholder: value
                 .get("placeholder")
                 .and_then(|s| s.as_str())
                 .unwrap_or("[[_REFS_]]")
                 .to_owned(),
+            local_placeholder: value
+                .get("local-placeholder")
+                .and_then(|s| s.as_str())
+                .unwrap_or("[[_LOCAL_REFS_]]")
+                .to_owned(),
+            sort: value
+                .get("sort")
+                .and_then(|s| s.as_str())
+                .map(Sort::from_str)
+                .transpose()?,
             render_key: value
                 .get("render-key")
                 .and_then(|s| s.as_bool())
@@ -65,6 +684,57 @@ impl TryFrom<&toml::Value> for Config {
                 .get("link-refs")
                 .and_then(|s| s.as_bool())
                 .unwrap_or(true),
+            backlinks: value
+                .get("backlinks")
+                .and_then(|s| s.as_str())
+                .map(Backlinks::from_str)
+                .unwrap_or(Ok(Backlinks::None))?,
+            tooltips: value
+                .get("tooltips")
+                .and_then(|s| s.as_str())
+                .map(Tooltips::from_str)
+                .unwrap_or(Ok(Tooltips::None))?,
+            disambiguate_names: value
+                .get("disambiguate-names")
+                .and_then(|s| s.as_bool())
+                .unwrap_or(false),
+            date_format: value
+                .get("date-format")
+                .and_then(|s| s.as_str())
+                .map(DateFormat::from_str)
+                .unwrap_or(Ok(DateFormat::Year))?,
+            terms: Terms::from_config(value.get("terms"))?,
+            names: Names::from_config(value.get("names"))?,
+            links: Links::from_config(value.get("links"))?,
+            numbers: Numbers::from_config(value.get("numbers"))?,
+            levels: Levels::from_config(
+                value.get("levels"),
+                value
+                    .get("strict")
+                    .and_then(|s| s.as_bool())
+                    .unwrap_or(false),
+            )?,
         })
     }
 }
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::try_from(&toml::Value::Table(Default::default())).unwrap()
+    }
+}
+
+/// Reads an option that can be given as a single string or as an array of strings.
+fn string_list(value: &toml::Value, key: &str) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    match value.get(key) {
+        None => Ok(None),
+        Some(toml::Value::String(s)) => Ok(Some(vec![s.clone()])),
+        Some(toml::Value::Array(values)) => values
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_owned()))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or_else(|| format!("Option '{}' must only contain strings", key).into()),
+        Some(_) => Err(format!("Option '{}' must be a string or an array of strings", key).into()),
+    }
+}
@@ -0,0 +1,195 @@
+//! Built-in `en-US` terms, used where the style does not define its own.
+
+use super::TermForm;
+
+/// Looks up a built-in term as `(single, multiple)`.
+pub fn term(name: &str, form: TermForm) -> Option<(&'static str, &'static str)> {
+    let term = match (name, form) {
+        ("and", TermForm::Symbol) => ("&", "&"),
+        ("and", _) => ("and", "and"),
+        ("et-al", _) => ("et al.", "et al."),
+        ("and others", _) => ("and others", "and others"),
+        ("accessed", _) => ("accessed", "accessed"),
+        ("anonymous", TermForm::Short) => ("anon.", "anon."),
+        ("anonymous", _) => ("anonymous", "anonymous"),
+        ("available at", _) => ("available at", "available at"),
+        ("by", _) => ("by", "by"),
+        ("forthcoming", _) => ("forthcoming", "forthcoming"),
+        ("from", _) => ("from", "from"),
+        ("ibid", _) => ("ibid.", "ibid."),
+        ("in", _) => ("in", "in"),
+        ("in press", _) => ("in press", "in press"),
+        ("no date", TermForm::Short) => ("n.d.", "n.d."),
+        ("no date", _) => ("no date", "no date"),
+        ("online", _) => ("online", "online"),
+        ("presented at", _) => ("presented at", "presented at"),
+        ("retrieved", _) => ("retrieved", "retrieved"),
+        ("open-quote", _) => ("“", "“"),
+        ("close-quote", _) => ("”", "”"),
+        ("page-range-delimiter", _) => ("–", "–"),
+
+        ("editor", TermForm::Short) => ("ed.", "eds."),
+        ("editor", TermForm::Verb) => ("edited by", "edited by"),
+        ("editor", TermForm::VerbShort) => ("ed.", "ed."),
+        ("editor", _) => ("editor", "editors"),
+        ("translator", TermForm::Short) => ("tran.", "trans."),
+        ("translator", TermForm::Verb) => ("translated by", "translated by"),
+        ("translator", TermForm::VerbShort) => ("trans.", "trans."),
+        ("translator", _) => ("translator", "translators"),
+        ("container-author", TermForm::Verb) => ("by", "by"),
+        ("collection-editor", TermForm::Short) => ("ed.", "eds."),
+        ("collection-editor", _) => ("editor", "editors"),
+
+        ("edition", TermForm::Short) => ("ed.", "eds."),
+        ("edition", _) => ("edition", "editions"),
+        ("issue", TermForm::Short) => ("no.", "nos."),
+        ("issue", _) => ("issue", "issues"),
+
+        ("book", TermForm::Short) => ("bk.", "bks."),
+        ("book", _) => ("book", "books"),
+        ("chapter", TermForm::Short) => ("chap.", "chaps."),
+        ("chapter", _) => ("chapter", "chapters"),
+        ("column", TermForm::Short) => ("col.", "cols."),
+        ("column", _) => ("column", "columns"),
+        ("figure", TermForm::Short) => ("fig.", "figs."),
+        ("figure", _) => ("figure", "figures"),
+        ("folio", TermForm::Short) => ("fol.", "fols."),
+        ("folio", _) => ("folio", "folios"),
+        ("line", TermForm::Short) => ("l.", "ll."),
+        ("line", _) => ("line", "lines"),
+        ("note", TermForm::Short) => ("n.", "nn."),
+        ("note", _) => ("note", "notes"),
+        ("number", TermForm::Short) => ("no.", "nos."),
+        ("number", _) => ("number", "numbers"),
+        ("opus", TermForm::Short) => ("op.", "opp."),
+        ("opus", _) => ("opus", "opera"),
+        ("page", TermForm::Short) => ("p.", "pp."),
+        ("page", _) => ("page", "pages"),
+        ("paragraph", TermForm::Short) => ("para.", "paras."),
+        ("paragraph", TermForm::Symbol) => ("¶", "¶¶"),
+        ("paragraph", _) => ("paragraph", "paragraphs"),
+        ("part", TermForm::Short) => ("pt.", "pts."),
+        ("part", _) => ("part", "parts"),
+        ("section", TermForm::Short) => ("sec.", "secs."),
+        ("section", TermForm::Symbol) => ("§", "§§"),
+        ("section", _) => ("section", "sections"),
+        ("verse", TermForm::Short) => ("v.", "vv."),
+        ("verse", _) => ("verse", "verses"),
+        ("volume", TermForm::Short) => ("vol.", "vols."),
+        ("volume", _) => ("volume", "volumes"),
+
+        ("month-01", TermForm::Short) => ("Jan.", "Jan."),
+        ("month-01", _) => ("January", "January"),
+        ("month-02", TermForm::Short) => ("Feb.", "Feb."),
+        ("month-02", _) => ("February", "February"),
+        ("month-03", TermForm::Short) => ("Mar.", "Mar."),
+        ("month-03", _) => ("March", "March"),
+        ("month-04", TermForm::Short) => ("Apr.", "Apr."),
+        ("month-04", _) => ("April", "April"),
+        ("month-05", _) => ("May", "May"),
+        ("month-06", _) => ("June", "June"),
+        ("month-07", _) => ("July", "July"),
+        ("month-08", TermForm::Short) => ("Aug.", "Aug."),
+        ("month-08", _) => ("August", "August"),
+        ("month-09", TermForm::Short) => ("Sep.", "Sep."),
+        ("month-09", _) => ("September", "September"),
+        ("month-10", TermForm::Short) => ("Oct.", "Oct."),
+        ("month-10", _) => ("October", "October"),
+        ("month-11", TermForm::Short) => ("Nov.", "Nov."),
+        ("month-11", _) => ("November", "November"),
+        ("month-12", TermForm::Short) => ("Dec.", "Dec."),
+        ("month-12", _) => ("December", "December"),
+        _ => return None,
+    };
+    Some(term)
+}
+
+/// Falls back to the next term form, as defined by the CSL specification.
+pub fn fallback(form: TermForm) -> Option<TermForm> {
+    match form {
+        TermForm::Long => None,
+        TermForm::Short => Some(TermForm::Long),
+        TermForm::Verb => Some(TermForm::Long),
+        TermForm::VerbShort => Some(TermForm::Verb),
+        TermForm::Symbol => Some(TermForm::Short),
+    }
+}
+
+/// Renders an English ordinal, like `2nd`.
+pub fn ordinal(number: i64) -> String {
+    let suffix = match (number % 10, number % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", number, suffix)
+}
+
+/// Renders an English long ordinal for numbers up to ten, like `second`.
+pub fn long_ordinal(number: i64) -> String {
+    const ORDINALS: [&str; 10] = [
+        "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+        "tenth",
+    ];
+    if (1..=10).contains(&number) {
+        ORDINALS[number as usize - 1].to_owned()
+    } else {
+        ordinal(number)
+    }
+}
+
+/// Renders a number as lower-case roman numerals.
+pub fn roman(mut number: i64) -> String {
+    const NUMERALS: [(i64, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    if number <= 0 || number >= 4000 {
+        return number.to_string();
+    }
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS.iter() {
+        while number >= *value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
+}
+
+/// Words not capitalized in title case, unless at the start.
+pub fn is_stop_word(word: &str) -> bool {
+    matches!(
+        word,
+        "a" | "an"
+            | "and"
+            | "as"
+            | "at"
+            | "but"
+            | "by"
+            | "for"
+            | "from"
+            | "in"
+            | "into"
+            | "nor"
+            | "of"
+            | "on"
+            | "or"
+            | "the"
+            | "to"
+            | "with"
+    )
+}
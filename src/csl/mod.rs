@@ -0,0 +1,715 @@
+//! A subset of the [Citation Style Language](https://citationstyles.org/) (CSL 1.0),
+//! evaluated locally against BibTeX entries.
+
+mod locale;
+mod render;
+
+pub use render::{render_bibliography, render_citation, render_cluster, sort_entries};
+
+use roxmltree::Node;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+
+#[derive(Debug, PartialEq)]
+pub struct Style {
+    pub numeric: bool,
+    macros: HashMap<String, Vec<Element>>,
+    citation: Layout,
+    bibliography: Option<Layout>,
+    terms: HashMap<(String, TermForm), (String, String)>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Layout {
+    elements: Vec<Element>,
+    formatting: Formatting,
+    delimiter: String,
+    sort: Vec<SortKey>,
+    names: NameOptions,
+}
+
+#[derive(Debug, PartialEq)]
+struct SortKey {
+    source: SortSource,
+    descending: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum SortSource {
+    Variable(String),
+    Macro(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Element {
+    Text(TextSource, Formatting),
+    Number(String, NumberForm, Formatting),
+    Label(String, TermForm, Plural, Formatting),
+    Names(Box<Names>),
+    Date(Box<DateElement>),
+    Group(Vec<Element>, String, Formatting),
+    Choose(Vec<(Condition, Vec<Element>)>, Vec<Element>),
+}
+
+#[derive(Debug, PartialEq)]
+enum TextSource {
+    Variable(String, VariableForm),
+    Macro(String),
+    Term(String, TermForm, bool),
+    Value(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum VariableForm {
+    Long,
+    Short,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum TermForm {
+    Long,
+    Short,
+    Verb,
+    VerbShort,
+    Symbol,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum NumberForm {
+    Numeric,
+    Ordinal,
+    LongOrdinal,
+    Roman,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Plural {
+    Contextual,
+    Always,
+    Never,
+}
+
+#[derive(Debug, PartialEq)]
+struct Names {
+    variables: Vec<String>,
+    name: Option<NameOptions>,
+    et_al: Option<String>,
+    label: Option<(TermForm, Plural, Formatting)>,
+    substitute: Vec<Element>,
+    delimiter: String,
+    formatting: Formatting,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+struct NameOptions {
+    and: Option<String>,
+    delimiter: Option<String>,
+    delimiter_precedes_last: Option<String>,
+    et_al_min: Option<usize>,
+    et_al_use_first: Option<usize>,
+    initialize_with: Option<String>,
+    initialize: Option<bool>,
+    name_as_sort_order: Option<String>,
+    sort_separator: Option<String>,
+    form: Option<String>,
+}
+
+impl NameOptions {
+    /// Fills options not set in `self` from `parent`.
+    fn inherit(&self, parent: &NameOptions) -> NameOptions {
+        NameOptions {
+            and: self.and.clone().or_else(|| parent.and.clone()),
+            delimiter: self.delimiter.clone().or_else(|| parent.delimiter.clone()),
+            delimiter_precedes_last: self
+                .delimiter_precedes_last
+                .clone()
+                .or_else(|| parent.delimiter_precedes_last.clone()),
+            et_al_min: self.et_al_min.or(parent.et_al_min),
+            et_al_use_first: self.et_al_use_first.or(parent.et_al_use_first),
+            initialize_with: self
+                .initialize_with
+                .clone()
+                .or_else(|| parent.initialize_with.clone()),
+            initialize: self.initialize.or(parent.initialize),
+            name_as_sort_order: self
+                .name_as_sort_order
+                .clone()
+                .or_else(|| parent.name_as_sort_order.clone()),
+            sort_separator: self
+                .sort_separator
+                .clone()
+                .or_else(|| parent.sort_separator.clone()),
+            form: self.form.clone().or_else(|| parent.form.clone()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct DateElement {
+    variable: String,
+    form: Option<DateForm>,
+    date_parts: String,
+    parts: Vec<DatePart>,
+    delimiter: String,
+    formatting: Formatting,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum DateForm {
+    Text,
+    Numeric,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct DatePart {
+    name: String,
+    form: String,
+    formatting: Formatting,
+}
+
+#[derive(Debug, PartialEq)]
+struct Condition {
+    tests: Vec<Test>,
+    match_: Match,
+}
+
+#[derive(Debug, PartialEq)]
+enum Test {
+    Type(String),
+    Variable(String),
+    IsNumeric(String),
+    Locator(String),
+    Position(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Match {
+    All,
+    Any,
+    None,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Formatting {
+    prefix: String,
+    suffix: String,
+    italic: bool,
+    bold: bool,
+    superscript: bool,
+    quotes: bool,
+    strip_periods: bool,
+    text_case: Option<String>,
+}
+
+/// Loads and parses a CSL style file.
+pub fn load_style<P: AsRef<Path>>(file: P) -> Result<Style, Box<dyn Error>> {
+    let content = std::fs::read_to_string(&file).map_err(|err| {
+        format!(
+            "Can't read CSL style from file {} - {}",
+            file.as_ref().display(),
+            err
+        )
+    })?;
+
+    parse_style(&content).map_err(|err| {
+        format!(
+            "Invalid CSL style in file {} - {}",
+            file.as_ref().display(),
+            err
+        )
+        .into()
+    })
+}
+
+fn parse_style(content: &str) -> Result<Style, Box<dyn Error>> {
+    let doc = roxmltree::Document::parse(content)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "style" {
+        return Err("root element must be <style>".into());
+    }
+
+    let style_names = parse_name_options(root);
+    let mut macros = HashMap::new();
+    let mut terms = HashMap::new();
+    let mut citation = None;
+    let mut bibliography = None;
+    let mut numeric = false;
+
+    for node in root.children().filter(|n| n.is_element()) {
+        match node.tag_name().name() {
+            "info" => {
+                numeric = node
+                    .descendants()
+                    .filter(|n| n.has_tag_name("category"))
+                    .any(|n| n.attribute("citation-format") == Some("numeric"));
+            }
+            "locale" => {
+                for term in node.descendants().filter(|n| n.has_tag_name("term")) {
+                    parse_term(term, &mut terms);
+                }
+            }
+            "macro" => {
+                let name = node
+                    .attribute("name")
+                    .ok_or("<macro> without a name")?
+                    .to_owned();
+                macros.insert(name, parse_children(node)?);
+            }
+            "citation" => citation = Some(parse_layout(node, &style_names)?),
+            "bibliography" => bibliography = Some(parse_layout(node, &style_names)?),
+            _ => {}
+        }
+    }
+
+    check_macro_cycles(&macros)?;
+
+    Ok(Style {
+        numeric,
+        macros,
+        citation: citation.ok_or("no <citation> element")?,
+        bibliography,
+        terms,
+    })
+}
+
+/// Reports a macro that calls itself, directly or through other macros,
+/// which would not terminate when rendering.
+fn check_macro_cycles(macros: &HashMap<String, Vec<Element>>) -> Result<(), Box<dyn Error>> {
+    fn visit(
+        name: &str,
+        macros: &HashMap<String, Vec<Element>>,
+        expanding: &mut Vec<String>,
+        checked: &mut HashSet<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        if checked.contains(name) {
+            return Ok(());
+        }
+        if let Some(start) = expanding.iter().position(|other| other == name) {
+            let mut cycle = expanding[start..].to_vec();
+            cycle.push(name.to_owned());
+            return Err(format!("recursive macro: {}", cycle.join(" -> ")).into());
+        }
+        if let Some(elements) = macros.get(name) {
+            expanding.push(name.to_owned());
+            let mut calls = vec![];
+            macro_calls(elements, &mut calls);
+            for call in calls {
+                visit(call, macros, expanding, checked)?;
+            }
+            expanding.pop();
+        }
+        checked.insert(name.to_owned());
+        Ok(())
+    }
+
+    let mut names: Vec<_> = macros.keys().collect();
+    names.sort();
+    let mut checked = HashSet::new();
+    for name in names {
+        visit(name, macros, &mut vec![], &mut checked)?;
+    }
+    Ok(())
+}
+
+/// Collects the names of the macros called by the elements.
+fn macro_calls<'a>(elements: &'a [Element], calls: &mut Vec<&'a str>) {
+    for element in elements {
+        match element {
+            Element::Text(TextSource::Macro(name), _) => calls.push(name),
+            Element::Names(names) => macro_calls(&names.substitute, calls),
+            Element::Group(children, _, _) => macro_calls(children, calls),
+            Element::Choose(branches, otherwise) => {
+                for (_, children) in branches {
+                    macro_calls(children, calls);
+                }
+                macro_calls(otherwise, calls);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_term(node: Node, terms: &mut HashMap<(String, TermForm), (String, String)>) {
+    if let Some(name) = node.attribute("name") {
+        let form = parse_term_form(node.attribute("form"));
+        let single = node
+            .children()
+            .find(|n| n.has_tag_name("single"))
+            .and_then(|n| n.text());
+        let multiple = node
+            .children()
+            .find(|n| n.has_tag_name("multiple"))
+            .and_then(|n| n.text());
+        let text = node.text().unwrap_or_default();
+        terms.insert(
+            (name.to_owned(), form),
+            (
+                single.unwrap_or(text).to_owned(),
+                multiple.or(single).unwrap_or(text).to_owned(),
+            ),
+        );
+    }
+}
+
+fn parse_layout(node: Node, parent: &NameOptions) -> Result<Layout, Box<dyn Error>> {
+    let layout = node
+        .children()
+        .find(|n| n.has_tag_name("layout"))
+        .ok_or_else(|| format!("<{}> without <layout>", node.tag_name().name()))?;
+
+    let sort = node
+        .children()
+        .find(|n| n.has_tag_name("sort"))
+        .map(|sort| {
+            sort.children()
+                .filter(|n| n.has_tag_name("key"))
+                .filter_map(|key| {
+                    let source = if let Some(var) = key.attribute("variable") {
+                        SortSource::Variable(var.to_owned())
+                    } else {
+                        SortSource::Macro(key.attribute("macro")?.to_owned())
+                    };
+                    Some(SortKey {
+                        source,
+                        descending: key.attribute("sort") == Some("descending"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(Layout {
+        elements: parse_children(layout)?,
+        formatting: parse_formatting(layout),
+        delimiter: layout.attribute("delimiter").unwrap_or_default().to_owned(),
+        sort,
+        names: parse_name_options(node).inherit(parent),
+    })
+}
+
+fn parse_children(node: Node) -> Result<Vec<Element>, Box<dyn Error>> {
+    node.children()
+        .filter(|n| n.is_element())
+        .map(parse_element)
+        .collect::<Result<Vec<_>, _>>()
+        .map(|elements| elements.into_iter().flatten().collect())
+}
+
+fn parse_element(node: Node) -> Result<Option<Element>, Box<dyn Error>> {
+    let formatting = parse_formatting(node);
+    let element = match node.tag_name().name() {
+        "text" => {
+            let source = if let Some(var) = node.attribute("variable") {
+                let form = match node.attribute("form") {
+                    Some("short") => VariableForm::Short,
+                    _ => VariableForm::Long,
+                };
+                TextSource::Variable(var.to_owned(), form)
+            } else if let Some(name) = node.attribute("macro") {
+                TextSource::Macro(name.to_owned())
+            } else if let Some(term) = node.attribute("term") {
+                TextSource::Term(
+                    term.to_owned(),
+                    parse_term_form(node.attribute("form")),
+                    node.attribute("plural") == Some("true"),
+                )
+            } else if let Some(value) = node.attribute("value") {
+                TextSource::Value(value.to_owned())
+            } else {
+                return Err("<text> without variable, macro, term or value".into());
+            };
+            Element::Text(source, formatting)
+        }
+        "number" => Element::Number(
+            node.attribute("variable")
+                .ok_or("<number> without a variable")?
+                .to_owned(),
+            match node.attribute("form") {
+                Some("ordinal") => NumberForm::Ordinal,
+                Some("long-ordinal") => NumberForm::LongOrdinal,
+                Some("roman") => NumberForm::Roman,
+                _ => NumberForm::Numeric,
+            },
+            formatting,
+        ),
+        "label" => Element::Label(
+            node.attribute("variable").unwrap_or("locator").to_owned(),
+            parse_term_form(node.attribute("form")),
+            parse_plural(node.attribute("plural")),
+            formatting,
+        ),
+        "names" => {
+            let name = node.children().find(|n| n.has_tag_name("name"));
+            Element::Names(Box::new(Names {
+                variables: node
+                    .attribute("variable")
+                    .ok_or("<names> without a variable")?
+                    .split_whitespace()
+                    .map(|s| s.to_owned())
+                    .collect(),
+                name: name.map(parse_name_options),
+                et_al: node
+                    .children()
+                    .find(|n| n.has_tag_name("et-al"))
+                    .map(|n| n.attribute("term").unwrap_or("et-al").to_owned()),
+                label: node.children().find(|n| n.has_tag_name("label")).map(|n| {
+                    (
+                        parse_term_form(n.attribute("form")),
+                        parse_plural(n.attribute("plural")),
+                        parse_formatting(n),
+                    )
+                }),
+                substitute: match node.children().find(|n| n.has_tag_name("substitute")) {
+                    Some(sub) => parse_children(sub)?,
+                    None => vec![],
+                },
+                delimiter: node.attribute("delimiter").unwrap_or(", ").to_owned(),
+                formatting,
+            }))
+        }
+        "date" => Element::Date(Box::new(DateElement {
+            variable: node
+                .attribute("variable")
+                .ok_or("<date> without a variable")?
+                .to_owned(),
+            form: match node.attribute("form") {
+                Some("text") => Some(DateForm::Text),
+                Some("numeric") => Some(DateForm::Numeric),
+                _ => None,
+            },
+            date_parts: node
+                .attribute("date-parts")
+                .unwrap_or("year-month-day")
+                .to_owned(),
+            parts: node
+                .children()
+                .filter(|n| n.has_tag_name("date-part"))
+                .filter_map(|n| {
+                    let name = n.attribute("name")?.to_owned();
+                    let form = n
+                        .attribute("form")
+                        .unwrap_or(if name == "month" { "long" } else { "numeric" })
+                        .to_owned();
+                    Some(DatePart {
+                        name,
+                        form,
+                        formatting: parse_formatting(n),
+                    })
+                })
+                .collect(),
+            delimiter: node.attribute("delimiter").unwrap_or_default().to_owned(),
+            formatting,
+        })),
+        "group" => Element::Group(
+            parse_children(node)?,
+            node.attribute("delimiter").unwrap_or_default().to_owned(),
+            formatting,
+        ),
+        "choose" => {
+            let mut branches = vec![];
+            let mut otherwise = vec![];
+            for branch in node.children().filter(|n| n.is_element()) {
+                match branch.tag_name().name() {
+                    "if" | "else-if" => {
+                        branches.push((parse_condition(branch), parse_children(branch)?))
+                    }
+                    "else" => otherwise = parse_children(branch)?,
+                    _ => {}
+                }
+            }
+            Element::Choose(branches, otherwise)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(element))
+}
+
+fn parse_condition(node: Node) -> Condition {
+    let mut tests = vec![];
+    let mut add = |attr: &str, test: fn(String) -> Test| {
+        if let Some(values) = node.attribute(attr) {
+            tests.extend(values.split_whitespace().map(|v| test(v.to_owned())));
+        }
+    };
+    add("type", Test::Type);
+    add("variable", Test::Variable);
+    add("is-numeric", Test::IsNumeric);
+    add("locator", Test::Locator);
+    add("position", Test::Position);
+
+    Condition {
+        tests,
+        match_: match node.attribute("match") {
+            Some("any") => Match::Any,
+            Some("none") => Match::None,
+            _ => Match::All,
+        },
+    }
+}
+
+fn parse_name_options(node: Node) -> NameOptions {
+    let string = |attr: &str| node.attribute(attr).map(|s| s.to_owned());
+    let number = |attr: &str| node.attribute(attr).and_then(|s| s.parse().ok());
+    NameOptions {
+        and: string("and"),
+        delimiter: if node.has_tag_name("name") {
+            string("delimiter")
+        } else {
+            string("name-delimiter")
+        },
+        delimiter_precedes_last: string("delimiter-precedes-last"),
+        et_al_min: number("et-al-min"),
+        et_al_use_first: number("et-al-use-first"),
+        initialize_with: string("initialize-with"),
+        initialize: node.attribute("initialize").map(|s| s == "true"),
+        name_as_sort_order: string("name-as-sort-order"),
+        sort_separator: string("sort-separator"),
+        form: if node.has_tag_name("name") {
+            string("form")
+        } else {
+            string("name-form")
+        },
+    }
+}
+
+fn parse_formatting(node: Node) -> Formatting {
+    Formatting {
+        prefix: node.attribute("prefix").unwrap_or_default().to_owned(),
+        suffix: node.attribute("suffix").unwrap_or_default().to_owned(),
+        italic: node.attribute("font-style") == Some("italic"),
+        bold: node.attribute("font-weight") == Some("bold"),
+        superscript: node.attribute("vertical-align") == Some("sup"),
+        quotes: node.attribute("quotes") == Some("true"),
+        strip_periods: node.attribute("strip-periods") == Some("true"),
+        text_case: node.attribute("text-case").map(|s| s.to_owned()),
+    }
+}
+
+fn parse_term_form(form: Option<&str>) -> TermForm {
+    match form {
+        Some("short") => TermForm::Short,
+        Some("verb") => TermForm::Verb,
+        Some("verb-short") => TermForm::VerbShort,
+        Some("symbol") => TermForm::Symbol,
+        _ => TermForm::Long,
+    }
+}
+
+fn parse_plural(plural: Option<&str>) -> Plural {
+    match plural {
+        Some("always") => Plural::Always,
+        Some("never") => Plural::Never,
+        _ => Plural::Contextual,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_style;
+
+    pub const TEST_STYLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<style xmlns="http://purl.org/net/xbiblio/csl" class="in-text" version="1.0">
+  <info>
+    <title>Test author-date</title>
+    <category citation-format="author-date"/>
+  </info>
+  <macro name="author">
+    <names variable="author">
+      <name name-as-sort-order="all" and="symbol" sort-separator=", " initialize-with=". " delimiter=", " delimiter-precedes-last="always"/>
+      <substitute>
+        <names variable="editor"/>
+        <text variable="title"/>
+      </substitute>
+    </names>
+  </macro>
+  <macro name="author-short">
+    <names variable="author">
+      <name form="short" and="symbol" delimiter=", "/>
+      <substitute>
+        <names variable="editor"/>
+        <text variable="title" font-style="italic"/>
+      </substitute>
+    </names>
+  </macro>
+  <macro name="year">
+    <choose>
+      <if variable="issued">
+        <date variable="issued">
+          <date-part name="year"/>
+        </date>
+      </if>
+      <else>
+        <text term="no date" form="short"/>
+      </else>
+    </choose>
+  </macro>
+  <citation et-al-min="3" et-al-use-first="1">
+    <layout prefix="(" suffix=")" delimiter="; ">
+      <group delimiter=", ">
+        <text macro="author-short"/>
+        <text macro="year"/>
+        <group delimiter=" ">
+          <label variable="locator" form="short"/>
+          <text variable="locator"/>
+        </group>
+      </group>
+    </layout>
+  </citation>
+  <bibliography>
+    <sort>
+      <key macro="author"/>
+      <key variable="issued"/>
+    </sort>
+    <layout suffix=".">
+      <group delimiter=". ">
+        <group delimiter=" ">
+          <text macro="author"/>
+          <text macro="year" prefix="(" suffix=")"/>
+        </group>
+        <text variable="title" font-style="italic"/>
+        <choose>
+          <if type="article-journal">
+            <group delimiter=", ">
+              <text variable="container-title"/>
+              <group>
+                <text variable="volume"/>
+                <text variable="issue" prefix="(" suffix=")"/>
+              </group>
+              <text variable="page"/>
+            </group>
+          </if>
+          <else>
+            <text variable="publisher"/>
+          </else>
+        </choose>
+      </group>
+    </layout>
+  </bibliography>
+</style>
+"#;
+
+    #[test]
+    fn parse_test_style() {
+        let style = parse_style(TEST_STYLE).unwrap();
+
+        assert!(!style.numeric);
+        assert_eq!(style.macros.len(), 3);
+        assert_eq!(style.citation.names.et_al_min, Some(3));
+        assert_eq!(style.bibliography.unwrap().sort.len(), 2);
+    }
+
+    #[test]
+    fn parse_invalid_style() {
+        assert!(parse_style("<style><info/></style>").is_err());
+        assert!(parse_style("<bibliography/>").is_err());
+
+        let recursive = r#"<style>
+  <macro name="a"><group><text macro="b"/></group></macro>
+  <macro name="b"><choose><if variable="title"><text macro="a"/></if></choose></macro>
+  <citation><layout><text macro="a"/></layout></citation>
+</style>"#;
+        let err = parse_style(recursive).unwrap_err();
+        assert_eq!(err.to_string(), "recursive macro: a -> b -> a");
+    }
+}
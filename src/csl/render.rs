@@ -0,0 +1,974 @@
+use super::locale;
+use super::{
+    Condition, DateElement, DateForm, DatePart, Element, Formatting, Layout, Match, NameOptions,
+    Names, NumberForm, Plural, SortSource, Style, TermForm, Test, TextSource, VariableForm,
+};
+use crate::citation::{CitationItem, CitePosition};
+use crate::disambiguate;
+use crate::format::{self, latex};
+use biblatex::{Date, DateValue, Datetime, Entry, EntryType, Person};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+struct Context<'a> {
+    style: &'a Style,
+    entry: &'a Entry,
+    index: usize,
+    item: Option<&'a CitationItem>,
+    position: CitePosition,
+    names: &'a NameOptions,
+    suppress_names: bool,
+    substituted: RefCell<Vec<String>>,
+}
+
+/// Rendered text, and whether it called any variables and whether any of them were non-empty.
+#[derive(Default)]
+struct Output {
+    text: String,
+    called: bool,
+    found: bool,
+}
+
+impl Output {
+    fn text(text: String) -> Self {
+        Output {
+            text,
+            called: false,
+            found: false,
+        }
+    }
+
+    fn variable(text: Option<String>) -> Self {
+        Output {
+            found: text.is_some(),
+            text: text.unwrap_or_default(),
+            called: true,
+        }
+    }
+}
+
+/// Renders a single cited item using the style's citation layout, without the layout's affixes.
+pub fn render_citation(
+    style: &Style,
+    entry: &Entry,
+    index: usize,
+    item: &CitationItem,
+    position: CitePosition,
+) -> String {
+    let mut ctx = Context::new(style, &style.citation, entry, index, Some(item));
+    ctx.position = position;
+    render_elements(&ctx, &style.citation.elements).text
+}
+
+/// Joins rendered items into a citation cluster, using the citation layout's delimiter and affixes.
+pub fn render_cluster(style: &Style, items: &[String]) -> String {
+    apply_formatting(
+        &style.citation.formatting,
+        items.join(&style.citation.delimiter),
+    )
+}
+
+/// Renders a reference list entry using the style's bibliography layout.
+pub fn render_bibliography(style: &Style, entry: &Entry, index: usize) -> String {
+    let layout = style.bibliography.as_ref().unwrap_or(&style.citation);
+    let ctx = Context::new(style, layout, entry, index, None);
+    apply_formatting(
+        &layout.formatting,
+        render_elements(&ctx, &layout.elements).text,
+    )
+}
+
+/// Sorts entries by the style's bibliography sort keys.
+/// Returns `false` and leaves the entries untouched if the style defines no sorting.
+pub fn sort_entries(style: &Style, entries: &mut Vec<(&Entry, usize)>) -> bool {
+    let layout = match &style.bibliography {
+        Some(layout) if !layout.sort.is_empty() => layout,
+        _ => return false,
+    };
+
+    entries.sort_by_cached_key(|(entry, index)| {
+        let ctx = Context::new(style, layout, entry, *index, None);
+        layout
+            .sort
+            .iter()
+            .map(|key| {
+                let value = match &key.source {
+                    SortSource::Variable(var) => sort_variable(&ctx, var),
+                    SortSource::Macro(name) => {
+                        let text = render_macro(&ctx, name).text;
+                        Some(text.replace('*', "").to_lowercase()).filter(|t| !t.is_empty())
+                    }
+                };
+                SortValue {
+                    value,
+                    descending: key.descending,
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+    true
+}
+
+#[derive(PartialEq, Eq)]
+struct SortValue {
+    value: Option<String>,
+    descending: bool,
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortValue {
+    /// Empty values are sorted last, irrespective of the sort direction.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.value, &other.value) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) if self.descending => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+        }
+    }
+}
+
+fn sort_variable(ctx: &Context, var: &str) -> Option<String> {
+    if let Some(persons) = name_variable(ctx.entry, var) {
+        return Some(
+            persons
+                .iter()
+                .map(|p| format!("{} {}", p.name, p.given_name).to_lowercase())
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+    }
+    if let Some(date) = date_variable(ctx.entry, var) {
+        let time = match date.value {
+            DateValue::At(time) | DateValue::After(time) | DateValue::Before(time) => time,
+            DateValue::Between(start, _) => start,
+        };
+        return Some(format!(
+            "{:+06}{:02}{:02}",
+            time.year,
+            time.month.unwrap_or(0),
+            time.day.unwrap_or(0)
+        ));
+    }
+    variable(ctx, var, VariableForm::Long).map(|v| v.to_lowercase())
+}
+
+impl<'a> Context<'a> {
+    fn new(
+        style: &'a Style,
+        layout: &'a Layout,
+        entry: &'a Entry,
+        index: usize,
+        item: Option<&'a CitationItem>,
+    ) -> Self {
+        Context {
+            style,
+            entry,
+            index,
+            item,
+            position: CitePosition::First,
+            names: &layout.names,
+            suppress_names: item.map(|item| item.no_author).unwrap_or(false),
+            substituted: RefCell::new(vec![]),
+        }
+    }
+
+    fn term(&self, name: &str, form: TermForm, plural: bool) -> String {
+        let mut form = Some(form);
+        while let Some(f) = form {
+            if let Some((single, multiple)) = self.style.terms.get(&(name.to_owned(), f)) {
+                return if plural { multiple } else { single }.clone();
+            }
+            if let Some((single, multiple)) = locale::term(name, f) {
+                return if plural { multiple } else { single }.to_owned();
+            }
+            form = locale::fallback(f);
+        }
+        String::new()
+    }
+
+    fn is_substituted(&self, var: &str) -> bool {
+        self.substituted.borrow().iter().any(|v| v == var)
+    }
+}
+
+fn render_elements(ctx: &Context, elements: &[Element]) -> Output {
+    let mut result = Output::default();
+    for element in elements {
+        let out = render_element(ctx, element);
+        result.text.push_str(&out.text);
+        result.called |= out.called;
+        result.found |= out.found;
+    }
+    result
+}
+
+fn render_macro(ctx: &Context, name: &str) -> Output {
+    match ctx.style.macros.get(name) {
+        Some(elements) => render_elements(ctx, elements),
+        None => Output::default(),
+    }
+}
+
+fn render_element(ctx: &Context, element: &Element) -> Output {
+    match element {
+        Element::Text(source, fmt) => {
+            let out = match source {
+                TextSource::Variable(var, _) if ctx.is_substituted(var) => Output::default(),
+                TextSource::Variable(var, form) => Output::variable(variable(ctx, var, *form)),
+                TextSource::Macro(name) => render_macro(ctx, name),
+                TextSource::Term(name, form, plural) => {
+                    Output::text(ctx.term(name, *form, *plural))
+                }
+                TextSource::Value(value) => Output::text(value.clone()),
+            };
+            with_formatting(fmt, out)
+        }
+        Element::Number(var, form, fmt) => {
+            let value = variable(ctx, var, VariableForm::Long).map(|value| {
+                match (form, value.trim().parse::<i64>()) {
+                    (NumberForm::Ordinal, Ok(num)) => locale::ordinal(num),
+                    (NumberForm::LongOrdinal, Ok(num)) => locale::long_ordinal(num),
+                    (NumberForm::Roman, Ok(num)) => locale::roman(num),
+                    _ => value,
+                }
+            });
+            with_formatting(fmt, Output::variable(value))
+        }
+        Element::Label(var, form, plural, fmt) => {
+            let text = label(ctx, var, *form, *plural).unwrap_or_default();
+            with_formatting(fmt, Output::text(text))
+        }
+        Element::Names(names) => render_names_element(ctx, names, None),
+        Element::Date(date) => render_date_element(ctx, date),
+        Element::Group(children, delimiter, fmt) => {
+            let mut called = false;
+            let mut found = false;
+            let mut parts = vec![];
+            for child in children {
+                let out = render_element(ctx, child);
+                called |= out.called;
+                found |= out.found;
+                if !out.text.is_empty() {
+                    parts.push(out.text);
+                }
+            }
+            if called && !found {
+                Output {
+                    text: String::new(),
+                    called,
+                    found,
+                }
+            } else {
+                Output {
+                    text: apply_formatting(fmt, parts.join(delimiter)),
+                    called,
+                    found,
+                }
+            }
+        }
+        Element::Choose(branches, otherwise) => {
+            let children = branches
+                .iter()
+                .find(|(condition, _)| evaluate(ctx, condition))
+                .map(|(_, children)| children)
+                .unwrap_or(otherwise);
+            render_elements(ctx, children)
+        }
+    }
+}
+
+fn with_formatting(fmt: &Formatting, out: Output) -> Output {
+    Output {
+        text: apply_formatting(fmt, out.text),
+        ..out
+    }
+}
+
+fn apply_formatting(fmt: &Formatting, text: String) -> String {
+    if text.is_empty() {
+        return text;
+    }
+    let mut text = match &fmt.text_case {
+        Some(case) => apply_text_case(case, &text),
+        None => text,
+    };
+    if fmt.strip_periods {
+        text = text.replace('.', "");
+    }
+    if fmt.quotes {
+        text = format!("“{}”", text);
+    }
+    if fmt.italic {
+        text = format!("*{}*", text);
+    }
+    if fmt.bold {
+        text = format!("**{}**", text);
+    }
+    if fmt.superscript {
+        text = format!("<sup>{}</sup>", text);
+    }
+    format!("{}{}{}", fmt.prefix, text, fmt.suffix)
+}
+
+fn apply_text_case(case: &str, text: &str) -> String {
+    match case {
+        "lowercase" => text.to_lowercase(),
+        "uppercase" => text.to_uppercase(),
+        "capitalize-first" | "sentence" => capitalize(text),
+        "capitalize-all" => text
+            .split(' ')
+            .map(capitalize)
+            .collect::<Vec<_>>()
+            .join(" "),
+        "title" => text
+            .split(' ')
+            .enumerate()
+            .map(|(idx, word)| {
+                if (idx > 0 && locale::is_stop_word(word)) || word.chars().any(char::is_uppercase) {
+                    word.to_owned()
+                } else {
+                    capitalize(word)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => text.to_owned(),
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn label(ctx: &Context, var: &str, form: TermForm, plural: Plural) -> Option<String> {
+    let (term, value) = match (var, ctx.item.and_then(|item| item.locator.as_ref())) {
+        ("locator", Some(locator)) => (locator.label.name(), locator.value.clone()),
+        ("locator", None) => return None,
+        _ => (var, variable(ctx, var, VariableForm::Long)?),
+    };
+    let plural = match plural {
+        Plural::Always => true,
+        Plural::Never => false,
+        Plural::Contextual => value.contains(&['-', '–', ',', '&'][..]),
+    };
+    Some(ctx.term(term, form, plural))
+}
+
+fn render_names_element(ctx: &Context, names: &Names, parent: Option<&Names>) -> Output {
+    if ctx.suppress_names {
+        return Output::default();
+    }
+
+    let options = match (&names.name, parent) {
+        (Some(options), _) => options.inherit(ctx.names),
+        (
+            None,
+            Some(Names {
+                name: Some(options),
+                ..
+            }),
+        ) => options.inherit(ctx.names),
+        (None, _) => NameOptions::default().inherit(ctx.names),
+    };
+    let label = names
+        .label
+        .as_ref()
+        .or_else(|| parent.and_then(|p| p.label.as_ref()));
+    let et_al = names
+        .et_al
+        .as_deref()
+        .or_else(|| parent.and_then(|p| p.et_al.as_deref()))
+        .unwrap_or("et-al");
+
+    let mut lists = vec![];
+    for var in names.variables.iter() {
+        if ctx.is_substituted(var) {
+            continue;
+        }
+        if let Some(persons) = name_variable(ctx.entry, var).filter(|p| !p.is_empty()) {
+            let mut text = render_names(ctx, &persons, &options, et_al);
+            if let Some((form, plural, fmt)) = label {
+                let plural = match plural {
+                    Plural::Always => true,
+                    Plural::Never => false,
+                    Plural::Contextual => persons.len() > 1,
+                };
+                text.push_str(&apply_formatting(fmt, ctx.term(var, *form, plural)));
+            }
+            lists.push(text);
+        }
+    }
+
+    if !lists.is_empty() {
+        return Output {
+            text: apply_formatting(&names.formatting, lists.join(&names.delimiter)),
+            called: true,
+            found: true,
+        };
+    }
+
+    for element in names.substitute.iter() {
+        let out = match element {
+            Element::Names(sub) => render_names_element(ctx, sub, Some(names)),
+            other => render_element(ctx, other),
+        };
+        if !out.text.is_empty() {
+            match element {
+                Element::Names(sub) => ctx
+                    .substituted
+                    .borrow_mut()
+                    .extend(sub.variables.iter().cloned()),
+                Element::Text(TextSource::Variable(var, _), _) => {
+                    ctx.substituted.borrow_mut().push(var.clone())
+                }
+                _ => {}
+            }
+            return Output {
+                text: apply_formatting(&names.formatting, out.text),
+                called: true,
+                found: true,
+            };
+        }
+    }
+
+    Output::variable(None)
+}
+
+fn render_names(ctx: &Context, persons: &[Person], options: &NameOptions, et_al: &str) -> String {
+    if options.form.as_deref() == Some("count") {
+        return persons.len().to_string();
+    }
+
+    let truncate = match (options.et_al_min, options.et_al_use_first) {
+        // Showing no names at all would leave only "et al."
+        (Some(min), Some(first)) if persons.len() >= min && first > 0 && first < persons.len() => {
+            Some(first)
+        }
+        _ => None,
+    };
+    let shown = &persons[..truncate.unwrap_or(persons.len())];
+    let delimiter = options.delimiter.as_deref().unwrap_or(", ");
+
+    let names: Vec<_> = shown
+        .iter()
+        .enumerate()
+        .map(|(idx, person)| {
+            let inverted = match options.name_as_sort_order.as_deref() {
+                Some("all") => true,
+                Some("first") => idx == 0,
+                _ => false,
+            };
+            render_name(person, options, inverted)
+        })
+        .collect();
+
+    let mut result = String::new();
+    for (idx, name) in names.iter().enumerate() {
+        if idx > 0 {
+            let is_last = idx == names.len() - 1 && truncate.is_none();
+            match (&options.and, is_last) {
+                (Some(and), true) => {
+                    let and = match and.as_str() {
+                        "symbol" => ctx.term("and", TermForm::Symbol, false),
+                        _ => ctx.term("and", TermForm::Long, false),
+                    };
+                    let use_delimiter = match options.delimiter_precedes_last.as_deref() {
+                        Some("always") => true,
+                        Some("never") => false,
+                        Some("after-inverted-name") => {
+                            options.name_as_sort_order.is_some() && (idx == 1 || names.len() > 2)
+                        }
+                        _ => names.len() > 2,
+                    };
+                    if use_delimiter {
+                        result.push_str(delimiter);
+                    } else {
+                        result.push(' ');
+                    }
+                    result.push_str(&and);
+                    result.push(' ');
+                }
+                _ => result.push_str(delimiter),
+            }
+        }
+        result.push_str(name);
+    }
+
+    if truncate.is_some() {
+        if shown.len() > 1 {
+            result.push_str(delimiter);
+        } else {
+            result.push(' ');
+        }
+        result.push_str(&ctx.term(et_al, TermForm::Long, false));
+    }
+
+    result
+}
+
+fn render_name(person: &Person, options: &NameOptions, inverted: bool) -> String {
+    let person = &Person {
+        name: latex::to_markdown(&person.name),
+        given_name: latex::to_markdown(&person.given_name),
+        prefix: latex::to_markdown(&person.prefix),
+        suffix: latex::to_markdown(&person.suffix),
+    };
+    let family = if person.prefix.is_empty() {
+        person.name.clone()
+    } else {
+        format!("{} {}", person.prefix, person.name)
+    };
+    if options.form.as_deref() == Some("short") {
+        return family;
+    }
+
+    let given = match (&options.initialize_with, options.initialize) {
+        (Some(with), init) if init != Some(false) => initials(&person.given_name, with),
+        _ => person.given_name.clone(),
+    };
+
+    let mut result = if given.is_empty() {
+        family
+    } else if inverted {
+        let sep = options.sort_separator.as_deref().unwrap_or(", ");
+        format!("{}{}{}", person.name, sep, given)
+            + &if person.prefix.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", person.prefix)
+            }
+    } else {
+        format!("{} {}", given, family)
+    };
+    if !person.suffix.is_empty() {
+        result.push_str(", ");
+        result.push_str(&person.suffix);
+    }
+    result
+}
+
+fn initials(given: &str, with: &str) -> String {
+    given
+        .split_whitespace()
+        .map(|part| {
+            part.split('-')
+                .filter_map(format::initial)
+                .map(|initial| format!("{}{}", initial.to_uppercase(), with.trim_end()))
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(if with.ends_with(' ') { " " } else { "" })
+}
+
+fn render_date_element(ctx: &Context, element: &DateElement) -> Output {
+    let date = match date_variable(ctx.entry, &element.variable) {
+        Some(date) => date,
+        None => return Output::variable(None),
+    };
+
+    let parts: Vec<DatePart> = match element.form {
+        Some(form) => localized_parts(form)
+            .into_iter()
+            .filter(|part| match element.date_parts.as_str() {
+                "year" => part.name == "year",
+                "year-month" => part.name != "day",
+                _ => true,
+            })
+            .map(|part| {
+                element
+                    .parts
+                    .iter()
+                    .find(|p| p.name == part.name)
+                    .map(|p| DatePart {
+                        formatting: Formatting {
+                            prefix: part.formatting.prefix.clone(),
+                            suffix: part.formatting.suffix.clone(),
+                            ..p.formatting.clone()
+                        },
+                        ..p.clone()
+                    })
+                    .unwrap_or(part)
+            })
+            .collect(),
+        None => element.parts.clone(),
+    };
+
+    // Year suffixes from disambiguation are appended to the year of the issued date
+    let year_suffix = if element.variable == "issued" {
+        disambiguate::year_suffix(ctx.entry)
+    } else {
+        String::new()
+    };
+    let render = |time: &Datetime, suffix: &str| {
+        let rendered: Vec<_> = parts
+            .iter()
+            .filter_map(|part| render_date_part(ctx, part, time, suffix))
+            .collect();
+        let mut text = rendered.join(&element.delimiter);
+        if element.form.is_some() {
+            text = text.trim_end_matches(&[',', '/', ' '][..]).to_owned();
+        }
+        text
+    };
+
+    let text = match &date.value {
+        DateValue::At(time) => render(time, &year_suffix),
+        DateValue::After(time) => format!("{}–", render(time, &year_suffix)),
+        DateValue::Before(time) => format!("–{}", render(time, &year_suffix)),
+        DateValue::Between(start, end) => {
+            format!("{}–{}", render(start, ""), render(end, &year_suffix))
+        }
+    };
+    Output {
+        text: apply_formatting(&element.formatting, text),
+        called: true,
+        found: true,
+    }
+}
+
+fn localized_parts(form: DateForm) -> Vec<DatePart> {
+    let part = |name: &str, form: &str, suffix: &str| DatePart {
+        name: name.to_owned(),
+        form: form.to_owned(),
+        formatting: Formatting {
+            suffix: suffix.to_owned(),
+            ..Formatting::default()
+        },
+    };
+    match form {
+        DateForm::Text => vec![
+            part("month", "long", " "),
+            part("day", "numeric", ", "),
+            part("year", "long", ""),
+        ],
+        DateForm::Numeric => vec![
+            part("month", "numeric", "/"),
+            part("day", "numeric", "/"),
+            part("year", "long", ""),
+        ],
+    }
+}
+
+fn render_date_part(
+    ctx: &Context,
+    part: &DatePart,
+    time: &Datetime,
+    year_suffix: &str,
+) -> Option<String> {
+    let text = match part.name.as_str() {
+        "year" => match part.form.as_str() {
+            "short" => format!("{:02}{}", time.year.rem_euclid(100), year_suffix),
+            _ => format!("{}{}", time.year, year_suffix),
+        },
+        "month" => {
+            let month = time.month? + 1;
+            match part.form.as_str() {
+                "numeric" => month.to_string(),
+                "numeric-leading-zeros" => format!("{:02}", month),
+                "short" => ctx.term(&format!("month-{:02}", month), TermForm::Short, false),
+                _ => ctx.term(&format!("month-{:02}", month), TermForm::Long, false),
+            }
+        }
+        "day" => {
+            let day = time.day? + 1;
+            match part.form.as_str() {
+                "numeric-leading-zeros" => format!("{:02}", day),
+                "ordinal" => locale::ordinal(day as i64),
+                _ => day.to_string(),
+            }
+        }
+        _ => return None,
+    };
+    Some(apply_formatting(&part.formatting, text))
+}
+
+fn evaluate(ctx: &Context, condition: &Condition) -> bool {
+    let mut results = condition.tests.iter().map(|test| match test {
+        Test::Type(tp) => csl_type(&ctx.entry.entry_type) == tp,
+        Test::Variable(var) => {
+            name_variable(ctx.entry, var).is_some_and(|p| !p.is_empty())
+                || date_variable(ctx.entry, var).is_some()
+                || variable(ctx, var, VariableForm::Long).is_some()
+        }
+        Test::IsNumeric(var) => variable(ctx, var, VariableForm::Long).is_some_and(|v| {
+            !v.is_empty()
+                && v.split(&['-', '–', ',', '&'][..])
+                    .all(|n| !n.trim().is_empty() && n.trim().chars().all(|c| c.is_ascii_digit()))
+        }),
+        Test::Locator(label) => ctx
+            .item
+            .and_then(|item| item.locator.as_ref())
+            .is_some_and(|locator| locator.label.name() == label),
+        Test::Position(position) => ctx.item.is_some() && ctx.position.matches(position),
+    });
+    match condition.match_ {
+        Match::All => results.all(|r| r),
+        Match::Any => results.any(|r| r),
+        Match::None => !results.any(|r| r),
+    }
+}
+
+/// Maps BibTeX entry types to CSL item types.
+fn csl_type(entry_type: &EntryType) -> &'static str {
+    match entry_type {
+        EntryType::Article => "article-journal",
+        EntryType::Book
+        | EntryType::MvBook
+        | EntryType::Booklet
+        | EntryType::Manual
+        | EntryType::Collection
+        | EntryType::MvCollection
+        | EntryType::Proceedings
+        | EntryType::MvProceedings
+        | EntryType::Reference
+        | EntryType::MvReference => "book",
+        EntryType::InBook
+        | EntryType::BookInBook
+        | EntryType::SuppBook
+        | EntryType::InCollection
+        | EntryType::SuppCollection => "chapter",
+        EntryType::InProceedings => "paper-conference",
+        EntryType::InReference => "entry-encyclopedia",
+        EntryType::Periodical | EntryType::SuppPeriodical => "periodical",
+        EntryType::Report | EntryType::TechReport => "report",
+        EntryType::Thesis | EntryType::PhdThesis | EntryType::MastersThesis => "thesis",
+        EntryType::Online => "webpage",
+        EntryType::Unpublished => "manuscript",
+        EntryType::Patent => "patent",
+        EntryType::Software => "software",
+        EntryType::Dataset => "dataset",
+        _ => "article",
+    }
+}
+
+fn name_variable(entry: &Entry, var: &str) -> Option<Vec<Person>> {
+    match var {
+        "author" => entry.author(),
+        "editor" => entry
+            .editors()
+            .into_iter()
+            .next()
+            .map(|(persons, _)| persons),
+        "translator" => entry.get_as::<Vec<Person>>("translator"),
+        "container-author" => entry.get_as::<Vec<Person>>("bookauthor"),
+        _ => None,
+    }
+}
+
+fn date_variable(entry: &Entry, var: &str) -> Option<Date> {
+    match var {
+        "issued" => entry.date(),
+        "accessed" => entry.get_as::<Date>("urldate"),
+        "original-date" => entry.get_as::<Date>("origdate"),
+        "event-date" => entry.get_as::<Date>("eventdate"),
+        _ => None,
+    }
+}
+
+fn variable(ctx: &Context, var: &str, form: VariableForm) -> Option<String> {
+    let entry = ctx.entry;
+    let field = |name: &str| entry.get(name).map(format::format_chunk);
+    let value = match var {
+        "citation-number" => Some(ctx.index.to_string()),
+        "citation-key" => Some(entry.key.clone()),
+        "locator" => ctx
+            .item
+            .and_then(|item| item.locator.as_ref())
+            .map(|locator| locator.value.clone()),
+        "title" => match form {
+            VariableForm::Short => field("shorttitle").or_else(|| field("title")),
+            VariableForm::Long => field("title"),
+        },
+        "container-title" => match form {
+            VariableForm::Short => field("shortjournal"),
+            VariableForm::Long => None,
+        }
+        .or_else(|| entry.journal().map(format::format_chunk))
+        .or_else(|| entry.book_title().map(format::format_chunk)),
+        "collection-title" => field("series"),
+        "publisher" => entry
+            .publisher()
+            .map(|publisher| format::format_chunks(&publisher, ", "))
+            .or_else(|| field("institution"))
+            .or_else(|| field("school"))
+            .or_else(|| field("organization")),
+        "publisher-place" => entry.address().map(format::format_chunk),
+        "event" | "event-title" => field("eventtitle"),
+        "genre" => field("type"),
+        "note" => field("note"),
+        "abstract" => field("abstract"),
+        "status" => field("pubstate"),
+        "URL" => entry.get("url").map(format::format_raw),
+        "DOI" => entry.get("doi").map(format::format_raw),
+        "ISBN" => field("isbn"),
+        "ISSN" => field("issn"),
+        "volume" => entry
+            .volume()
+            .map(|volume| volume.to_string())
+            .or_else(|| field("volume")),
+        "issue" | "number" => entry.number().map(format::format_chunk),
+        "edition" => field("edition"),
+        "chapter-number" => field("chapter"),
+        "number-of-pages" => field("pagetotal"),
+        "page" => match entry.pages() {
+            Some(ranges) if !ranges.is_empty() => Some(
+                ranges
+                    .iter()
+                    .map(|range| {
+                        if range.start == range.end {
+                            range.start.to_string()
+                        } else {
+                            format!("{}–{}", range.start, range.end)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            _ => field("pages"),
+        },
+        _ => None,
+    };
+    value.filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::citation::{parse_citations, CitePosition};
+    use crate::csl::parse_style;
+    use crate::csl::test::TEST_STYLE;
+    use biblatex::Bibliography;
+
+    const TEST_BIB: &str = r#"
+@book{Klabnik2018,
+    author = {Klabnik, Steve and Nichols, Carol},
+    title = {The Rust Programming Language},
+    year = {2018},
+    publisher = {No Starch Press},
+}
+@article{Knuth1984,
+    author = {Knuth, Donald E.},
+    title = {Literate Programming},
+    journal = {The Computer Journal},
+    volume = {27},
+    number = {2},
+    pages = {97--111},
+    year = {1984},
+}
+@book{Anon,
+    title = {A Book Without Author},
+}
+"#;
+
+    #[test]
+    fn render_citation() {
+        let style = parse_style(TEST_STYLE).unwrap();
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let (_, citation) = parse_citations("[@Klabnik2018, p. 5; -@Knuth1984; @Anon]")
+            .pop()
+            .unwrap();
+
+        let items: Vec<_> = citation
+            .items
+            .iter()
+            .map(|item| {
+                let entry = bib.get(&item.key).unwrap();
+                super::render_citation(&style, entry, 1, item, CitePosition::First)
+            })
+            .collect();
+
+        assert_eq!(items[0], "Klabnik & Nichols, 2018, p. 5");
+        assert_eq!(items[1], "1984");
+        assert_eq!(items[2], "*A Book Without Author*, n.d.");
+        assert_eq!(
+            super::render_cluster(&style, &items[..2]),
+            "(Klabnik & Nichols, 2018, p. 5; 1984)"
+        );
+    }
+
+    #[test]
+    fn render_positions() {
+        let style = TEST_STYLE.replace(
+            r#"<text macro="author-short"/>"#,
+            r#"<choose>
+              <if position="ibid-with-locator"><text value="ibid. with locator"/></if>
+              <else-if position="ibid"><text value="ibid."/></else-if>
+              <else-if position="subsequent"><text value="later"/></else-if>
+              <else><text macro="author-short"/></else>
+            </choose>"#,
+        );
+        let style = parse_style(&style).unwrap();
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let entry = bib.get("Knuth1984").unwrap();
+        let (_, citation) = parse_citations("[@Knuth1984]").pop().unwrap();
+        let item = &citation.items[0];
+
+        let render = |position| super::render_citation(&style, entry, 1, item, position);
+        assert_eq!(render(CitePosition::First), "Knuth, 1984");
+        assert_eq!(render(CitePosition::Subsequent), "later, 1984");
+        assert_eq!(render(CitePosition::Ibid), "ibid., 1984");
+        assert_eq!(
+            render(CitePosition::IbidWithLocator),
+            "ibid. with locator, 1984"
+        );
+    }
+
+    #[test]
+    fn render_et_al() {
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let entry = bib.get("Klabnik2018").unwrap();
+        let (_, citation) = parse_citations("[@Klabnik2018]").pop().unwrap();
+        let render = |first: &str| {
+            let style = TEST_STYLE.replace(
+                r#"et-al-min="3" et-al-use-first="1""#,
+                &format!(r#"et-al-min="2" et-al-use-first="{}""#, first),
+            );
+            let style = parse_style(&style).unwrap();
+            super::render_citation(&style, entry, 1, &citation.items[0], CitePosition::First)
+        };
+
+        assert_eq!(render("1"), "Klabnik et al., 2018");
+        assert_eq!(render("0"), "Klabnik & Nichols, 2018");
+    }
+
+    #[test]
+    fn render_bibliography() {
+        let style = parse_style(TEST_STYLE).unwrap();
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+
+        assert_eq!(
+            super::render_bibliography(&style, bib.get("Klabnik2018").unwrap(), 1),
+            "Klabnik, S., & Nichols, C. (2018). *The Rust Programming Language*. No Starch Press."
+        );
+        assert_eq!(
+            super::render_bibliography(&style, bib.get("Knuth1984").unwrap(), 1),
+            "Knuth, D. E. (1984). *Literate Programming*. The Computer Journal, 27(2), 97–111."
+        );
+        assert_eq!(
+            super::render_bibliography(&style, bib.get("Anon").unwrap(), 1),
+            "A Book Without Author (n.d.)."
+        );
+    }
+
+    #[test]
+    fn sort_entries() {
+        let style = parse_style(TEST_STYLE).unwrap();
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let mut entries: Vec<_> = bib.iter().enumerate().map(|(i, e)| (e, i)).collect();
+
+        assert!(super::sort_entries(&style, &mut entries));
+        let keys: Vec<_> = entries.iter().map(|(e, _)| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["Anon", "Klabnik2018", "Knuth1984"]);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::config::{Level, Levels};
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Categories of problems, with configurable levels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    UnknownKey,
+    MissingPlaceholder,
+    DuplicateKey,
+    UnsupportedType,
+    /// A bibliography pattern matching no files, or a document in `document-order` that does not exist
+    MissingFile,
+    /// Content of a bibliography file that is not imported
+    IgnoredContent,
+}
+
+/// Collects problems, printing warnings and keeping errors for a final summary.
+pub struct Diagnostics {
+    levels: Levels,
+    errors: Vec<String>,
+}
+
+impl Diagnostics {
+    pub fn new(levels: Levels) -> Self {
+        Diagnostics {
+            levels,
+            errors: vec![],
+        }
+    }
+
+    pub fn report(&mut self, category: Category, message: String) {
+        let level = match category {
+            Category::UnknownKey => self.levels.unknown_key,
+            Category::MissingPlaceholder => self.levels.missing_placeholder,
+            Category::DuplicateKey => self.levels.duplicate_key,
+            Category::UnsupportedType => self.levels.unsupported_type,
+            Category::MissingFile => self.levels.missing_file,
+            Category::IgnoredContent => self.levels.ignored_content,
+        };
+        match level {
+            Level::Ignore => {}
+            Level::Warn => eprintln!("  Warning: {}", message),
+            Level::Error => {
+                if !self.errors.contains(&message) {
+                    self.errors.push(message);
+                }
+            }
+        }
+    }
+
+    /// Fails with a summary of all problems reported as errors.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        let mut summary = format!("{} problem(s) found:", self.errors.len());
+        for error in &self.errors {
+            summary.push_str("\n  ");
+            summary.push_str(error);
+        }
+        Err(summary.into())
+    }
+}
+
+/// A position in a document, for warnings and errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub path: PathBuf,
+    /// Index of the block in the document, starting at 1
+    pub block: usize,
+    /// Index of the line in the block, starting at 1
+    pub line: usize,
+}
+
+impl Position {
+    pub fn new(path: &Path, block_idx: usize, line_idx: usize) -> Self {
+        Position {
+            path: path.to_owned(),
+            block: block_idx + 1,
+            line: line_idx + 1,
+        }
+    }
+
+    /// The position of another line in the same block.
+    pub fn at_line(&self, line_idx: usize) -> Self {
+        Position {
+            line: line_idx + 1,
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, block {}, line {}",
+            self.path.display(),
+            self.block,
+            self.line
+        )
+    }
+}
+
+/// Finds the candidate most similar to `key`, if any is similar enough to be a likely typo.
+pub fn suggest<'a, I>(key: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let key_lower = key.to_lowercase();
+    let max_distance = (key.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                edit_distance(&key_lower, &candidate.to_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Formats a "did you mean" hint for a suggested key, or an empty string.
+pub fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion
+        .map(|key| format!(" Did you mean '{}'?", key))
+        .unwrap_or_default()
+}
+
+/// The Levenshtein distance between two strings, in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Category, Diagnostics};
+    use crate::config::{Level, Levels};
+
+    #[test]
+    fn diagnostics_summary() {
+        let mut diagnostics = Diagnostics::new(Levels {
+            unknown_key: Level::Error,
+            missing_placeholder: Level::Ignore,
+            duplicate_key: Level::Warn,
+            unsupported_type: Level::Error,
+            missing_file: Level::Warn,
+            ignored_content: Level::Ignore,
+        });
+        diagnostics.report(
+            Category::UnknownKey,
+            "citation key '@A' not found".to_string(),
+        );
+        diagnostics.report(Category::MissingPlaceholder, "no placeholder".to_string());
+        diagnostics.report(
+            Category::UnknownKey,
+            "citation key '@B' not found".to_string(),
+        );
+        diagnostics.report(
+            Category::UnknownKey,
+            "citation key '@B' not found".to_string(),
+        );
+
+        assert_eq!(
+            diagnostics.finish().unwrap_err().to_string(),
+            "2 problem(s) found:\n  citation key '@A' not found\n  citation key '@B' not found"
+        );
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("Knuth1984", "Knuth1984"), 0);
+        assert_eq!(super::edit_distance("Knuth1948", "Knuth1984"), 2);
+        assert_eq!(super::edit_distance("Knth1984", "Knuth1984"), 1);
+        assert_eq!(super::edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggest() {
+        let keys = ["Knuth1984", "Baumer2015", "Klabnik2018"];
+        assert_eq!(
+            super::suggest("knuth1984", keys.iter().copied()),
+            Some("Knuth1984")
+        );
+        assert_eq!(
+            super::suggest("Baumer2016", keys.iter().copied()),
+            Some("Baumer2015")
+        );
+        assert_eq!(super::suggest("Doe2020", keys.iter().copied()), None);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::config::{CitationStyle, Config, DateFormat};
+use crate::format::{self, latex};
+use biblatex::{Bibliography, Chunk, Entry, Person};
+use std::collections::HashMap;
+
+/// Disambiguates author-year citations of the given keys.
+///
+/// Entries with identical labels get a year suffix (`2018a`, `2018b`), stored in field
+/// `extradate` like Biber does. With option `disambiguate-names`, authors with the same family
+/// name get initials or full given names first, stored in field `uniquename`.
+///
+/// Returns a copy of the bibliography, with the affected entries updated.
+pub fn disambiguate<S: AsRef<str>>(
+    bibliography: &Bibliography,
+    keys: &[S],
+    config: &Config,
+) -> Bibliography {
+    let mut result = bibliography.clone();
+    let applies = match &config.citation_style {
+        CitationStyle::Index | CitationStyle::Notes => false,
+        CitationStyle::AuthorYear => true,
+        CitationStyle::Csl(style) => !style.numeric,
+    };
+    if !applies {
+        return result;
+    }
+
+    let mut entries: Vec<Entry> = vec![];
+    for key in keys {
+        if let Some(entry) = bibliography.get(key.as_ref()) {
+            if entries.iter().all(|e| e.key != entry.key) {
+                entries.push(entry.clone());
+            }
+        }
+    }
+
+    if config.disambiguate_names {
+        let levels: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                cited_authors(&format::creator_names(entry).unwrap_or_default(), config)
+                    .iter()
+                    .map(|person| name_level(person, &entries, config))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (entry, levels) in entries.iter_mut().zip(levels) {
+            if levels.iter().any(|level| *level > 0) {
+                let levels: Vec<_> = levels.iter().map(|level| level.to_string()).collect();
+                entry.set("uniquename", vec![Chunk::Normal(levels.join(","))]);
+            }
+        }
+    }
+
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        let label = format!(
+            "{} {}",
+            format::format_authors_citation(entry, config),
+            format::format_date(entry, DateFormat::Year, config)
+        );
+        groups.entry(label).or_default().push(idx);
+    }
+    for group in groups.values_mut().filter(|group| group.len() > 1) {
+        group.sort_by_cached_key(|idx| {
+            let entry = &entries[*idx];
+            (
+                format::format_creators_opt(entry, config),
+                entry.title().map(format::format_chunk).unwrap_or_default(),
+                entry.key.clone(),
+            )
+        });
+        for (number, idx) in group.iter().enumerate() {
+            entries[*idx].set("extradate", vec![Chunk::Normal((number + 1).to_string())]);
+        }
+    }
+
+    for entry in entries {
+        if entry.get("extradate").is_some() || entry.get("uniquename").is_some() {
+            result.insert(entry);
+        }
+    }
+    result
+}
+
+/// The authors shown in an author-year citation, truncated for "et al." if required.
+pub fn cited_authors<'a>(authors: &'a [Person], config: &Config) -> &'a [Person] {
+    let names = &config.names;
+    if authors.len() >= names.et_al_min {
+        &authors[..names.et_al_use_first.min(authors.len())]
+    } else {
+        authors
+    }
+}
+
+/// How much of a person's given name is required to distinguish them from other cited authors:
+/// 0 for none, 1 for initials and 2 for the full given name.
+fn name_level(person: &Person, entries: &[Entry], config: &Config) -> u8 {
+    let others: Vec<_> = entries
+        .iter()
+        .flat_map(|entry| {
+            cited_authors(&format::creator_names(entry).unwrap_or_default(), config).to_vec()
+        })
+        .filter(|other| other.name == person.name && other.given_name != person.given_name)
+        .collect();
+    if others.is_empty() {
+        0
+    } else if others
+        .iter()
+        .all(|other| initials(&other.given_name) != initials(&person.given_name))
+    {
+        1
+    } else {
+        2
+    }
+}
+
+fn initials(given: &str) -> String {
+    format::initials(given, false, true).concat()
+}
+
+/// The disambiguation levels of an entry's cited authors, see [`disambiguate`].
+pub fn name_levels(entry: &Entry) -> Vec<u8> {
+    entry
+        .get("uniquename")
+        .map(format::format_chunk)
+        .map(|levels| {
+            levels
+                .split(',')
+                .map(|level| level.trim().parse().unwrap_or(0))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Formats an author for a citation, with the given disambiguation level.
+pub fn format_name(person: &Person, level: u8, config: &Config) -> String {
+    let mut name = latex::to_markdown(&person.name);
+    if config.names.use_prefix && !person.prefix.is_empty() {
+        name = format!("{} {}", latex::to_markdown(&person.prefix), name);
+    }
+    let given_name = latex::to_markdown(&person.given_name);
+    match level {
+        0 => name,
+        1 => {
+            let initials = format::initials(&given_name, true, config.names.initials_hyphen);
+            format!("{} {}", initials.join(" "), name)
+        }
+        _ => format!("{} {}", given_name, name),
+    }
+}
+
+/// The year suffix letter(s) of an entry: `a` to `z`, then `aa`, `ab`, ...
+pub fn year_suffix(entry: &Entry) -> String {
+    let mut number = match entry
+        .get("extradate")
+        .map(format::format_chunk)
+        .and_then(|n| n.parse::<u32>().ok())
+    {
+        Some(number) if number > 0 => number,
+        _ => return String::new(),
+    };
+    let mut suffix = vec![];
+    while number > 0 {
+        number -= 1;
+        suffix.push((b'a' + (number % 26) as u8) as char);
+        number /= 26;
+    }
+    suffix.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::{CitationStyle, Config};
+    use crate::format;
+    use biblatex::Bibliography;
+
+    const TEST_BIB: &str = r#"
+@book{Klabnik2018b,
+    author = {Klabnik, Steve and Nichols, Carol},
+    title = {The Rust Programming Language, Second Edition},
+    year = {2018},
+}
+@book{Klabnik2018a,
+    author = {Klabnik, Steve and Nichols, Carol},
+    title = {The Rust Programming Language},
+    year = {2018},
+}
+@book{SmithJ2020,
+    author = {Smith, John},
+    title = {A Book},
+    year = {2020},
+}
+@book{SmithA2020,
+    author = {Smith, Anne},
+    title = {Another Book},
+    year = {2020},
+}
+@book{SmithJ2021,
+    author = {Smith, Jane},
+    title = {Yet Another Book},
+    year = {2021},
+}
+"#;
+
+    #[test]
+    fn year_suffix() {
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            ..Config::default()
+        };
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let keys = ["Klabnik2018a", "Klabnik2018b", "SmithJ2020", "SmithA2020"];
+        let bib = super::disambiguate(&bib, &keys, &config);
+
+        let label = |key: &str| {
+            let entry = bib.get(key).unwrap();
+            format!(
+                "{} {}",
+                format::format_authors_citation(entry, &config),
+                format::format_year(entry, &config)
+            )
+        };
+        assert_eq!(label("Klabnik2018a"), "Klabnik & Nichols 2018a");
+        assert_eq!(label("Klabnik2018b"), "Klabnik & Nichols 2018b");
+        assert_eq!(label("SmithA2020"), "Smith 2020a");
+        assert_eq!(label("SmithJ2020"), "Smith 2020b");
+    }
+
+    #[test]
+    fn given_names() {
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            disambiguate_names: true,
+            ..Config::default()
+        };
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let keys = ["SmithJ2020", "SmithA2020", "SmithJ2021"];
+        let bib = super::disambiguate(&bib, &keys, &config);
+
+        let label = |key: &str| format::format_authors_citation(bib.get(key).unwrap(), &config);
+        assert_eq!(label("SmithJ2020"), "John Smith");
+        assert_eq!(label("SmithA2020"), "A. Smith");
+        assert_eq!(label("SmithJ2021"), "Jane Smith");
+        assert_eq!(super::year_suffix(bib.get("SmithJ2020").unwrap()), "");
+    }
+}
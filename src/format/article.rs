@@ -1,29 +1,23 @@
+use crate::config::Config;
 use crate::format::EntryFormatter;
-use biblatex::{ChunksExt, Entry};
+use biblatex::Entry;
 use std::fmt::Write;
 
 pub struct ArticleFormatter {}
 
 impl EntryFormatter for ArticleFormatter {
-    fn format(&self, result: &mut dyn Write, item: &Entry) {
-        write!(
-            result,
-            "{} ({}): **{}**",
-            super::format_authors_opt(item.author().as_ref()),
-            super::format_date(item.date()),
-            super::format_chunk_opt(item.title(), "Untitled"),
-        )
-        .unwrap();
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
 
         if let Some(chunks) = item.journal() {
-            write!(result, ". *{}*", chunks.format_verbatim()).unwrap();
+            write!(result, ". *{}*", super::format_chunk(chunks)).unwrap();
         }
 
         if let Some(volume) = item.volume() {
             write!(result, " {}", volume).unwrap();
 
             if let Some(number) = item.number() {
-                write!(result, ":{}", number.format_verbatim()).unwrap();
+                write!(result, ":{}", super::format_chunk(number)).unwrap();
             }
         }
 
@@ -0,0 +1,28 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::Entry;
+use std::fmt::Write;
+
+pub struct DatasetFormatter {}
+
+impl EntryFormatter for DatasetFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        if let Some(version) = super::format_field(item, "version") {
+            write!(result, ". Version {}", version).unwrap();
+        }
+
+        write!(result, " [Data set]").unwrap();
+
+        if let Some(chunks) = item.publisher() {
+            write!(result, ". *{}*", super::format_chunks(&chunks, ", ")).unwrap();
+        } else if let Some(organization) = super::format_field(item, "organization") {
+            write!(result, ". *{}*", organization).unwrap();
+        }
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::Config;
 use crate::format::EntryFormatter;
 use biblatex::Entry;
 use std::fmt::Write;
@@ -5,21 +6,17 @@ use std::fmt::Write;
 pub struct InCollectionFormatter {}
 
 impl EntryFormatter for InCollectionFormatter {
-    fn format(&self, result: &mut dyn Write, item: &Entry) {
-        write!(
-            result,
-            "{} ({}): **{}**",
-            super::format_authors_opt(item.author().as_ref()),
-            super::format_date(item.date()),
-            super::format_chunk_opt(item.title(), "Untitled"),
-        )
-        .unwrap();
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
 
+        write!(result, ". In: ").unwrap();
+        if let Some((editors, _)) = item.editors().first() {
+            write!(result, "{}: ", super::format_editors(editors, config)).unwrap();
+        }
         write!(
             result,
-            ". In: {} (eds.): {}",
-            super::format_authors_opt(item.editors().get(0).map(|e| &e.0)),
-            super::format_chunk_opt(item.book_title(), "Untitled"),
+            "{}",
+            super::format_chunk_opt(item.book_title(), "Untitled")
         )
         .unwrap();
 
@@ -32,7 +29,7 @@ impl EntryFormatter for InCollectionFormatter {
         }
 
         if let Some(chunks) = item.address() {
-            write!(result, ", {}", super::format_chunk(&chunks)).unwrap();
+            write!(result, ", {}", super::format_chunk(chunks)).unwrap();
         }
 
         write!(result, ".").unwrap();
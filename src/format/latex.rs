@@ -0,0 +1,622 @@
+//! Conversion of LaTeX markup in field values to Unicode and Markdown.
+
+/// Accent commands, with the letters they can be applied to and the resulting characters.
+const ACCENTS: &[(char, &str, &str)] = &[
+    ('"', "aeiouyAEIOUYthHwWxXı", "äëïöüÿÄËÏÖÜŸẗḧḦẅẄẍẌï"),
+    (
+        '\'',
+        "aeiouyAEIOUYcCnNsSzZgGrRlLkKwWmMpPı",
+        "áéíóúýÁÉÍÓÚÝćĆńŃśŚźŹǵǴŕŔĺĹḱḰẃẂḿḾṕṔí",
+    ),
+    ('`', "aeiouyAEIOUYnNwWı", "àèìòùỳÀÈÌÒÙỲǹǸẁẀì"),
+    (
+        '^',
+        "aeiouyAEIOUYcCsSzZgGhHjJwWı",
+        "âêîôûŷÂÊÎÔÛŶĉĈŝŜẑẐĝĜĥĤĵĴŵŴî",
+    ),
+    ('~', "aeiouyAEIOUYnNvVı", "ãẽĩõũỹÃẼĨÕŨỸñÑṽṼĩ"),
+    ('=', "aeiouyAEIOUYgGı", "āēīōūȳĀĒĪŌŪȲḡḠī"),
+    (
+        '.',
+        "aeoyAEIOYcCnNsSzZgGrRtTdDhHwWbBmMpPxX",
+        "ȧėȯẏȦĖİȮẎċĊṅṄṡṠżŻġĠṙṘṫṪḋḊḣḢẇẆḃḂṁṀṗṖẋẊ",
+    ),
+    ('u', "aeiouAEIOUgGı", "ăĕĭŏŭĂĔĬŎŬğĞĭ"),
+    (
+        'v',
+        "aeiouAEIOUcCnNsSzZgGrRlLtTdDkKhHjı",
+        "ǎěǐǒǔǍĚǏǑǓčČňŇšŠžŽǧǦřŘľĽťŤďĎǩǨȟȞǰǐ",
+    ),
+    ('H', "ouOU", "őűŐŰ"),
+    ('c', "eEcCnNsSgGrRlLtTdDkKhH", "ȩȨçÇņŅşŞģĢŗŖļĻţŢḑḐķĶḩḨ"),
+    ('k', "aeiouAEIOUı", "ąęįǫųĄĘĮǪŲį"),
+    ('r', "auyAUw", "åůẙÅŮẘ"),
+    (
+        'd',
+        "aeiouyAEIOUYnNsSzZrRlLtTdDkKhHwWbBmMvVı",
+        "ạẹịọụỵẠẸỊỌỤỴṇṆṣṢẓẒṛṚḷḶṭṬḍḌḳḲḥḤẉẈḅḄṃṂṿṾị",
+    ),
+];
+
+/// Commands for special characters.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("ss", "ß"),
+    ("SS", "SS"),
+    ("o", "ø"),
+    ("O", "Ø"),
+    ("ae", "æ"),
+    ("AE", "Æ"),
+    ("oe", "œ"),
+    ("OE", "Œ"),
+    ("aa", "å"),
+    ("AA", "Å"),
+    ("l", "ł"),
+    ("L", "Ł"),
+    ("i", "ı"),
+    ("j", "ȷ"),
+    ("dh", "ð"),
+    ("DH", "Ð"),
+    ("th", "þ"),
+    ("TH", "Þ"),
+    ("ng", "ŋ"),
+    ("NG", "Ŋ"),
+    ("S", "§"),
+    ("P", "¶"),
+    ("dag", "†"),
+    ("ddag", "‡"),
+    ("copyright", "©"),
+    ("textregistered", "®"),
+    ("texttrademark", "™"),
+    ("pounds", "£"),
+    ("euro", "€"),
+    ("textdegree", "°"),
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("textellipsis", "…"),
+    ("textendash", "–"),
+    ("textemdash", "—"),
+    ("textquoteleft", "‘"),
+    ("textquoteright", "’"),
+    ("textquotedblleft", "“"),
+    ("textquotedblright", "”"),
+    ("guillemotleft", "«"),
+    ("guillemotright", "»"),
+    ("textasciitilde", "~"),
+    ("textasciicircum", "^"),
+    ("textbackslash", "\\"),
+    ("textbar", "|"),
+    ("textless", "<"),
+    ("textgreater", ">"),
+    ("textunderscore", "_"),
+    ("LaTeX", "LaTeX"),
+    ("TeX", "TeX"),
+    ("BibTeX", "BibTeX"),
+];
+
+/// Commands in math mode.
+const MATH_SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ϵ"),
+    ("varepsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("vartheta", "ϑ"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("upsilon", "υ"),
+    ("phi", "ϕ"),
+    ("varphi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Xi", "Ξ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Upsilon", "Υ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("times", "×"),
+    ("cdot", "·"),
+    ("pm", "±"),
+    ("mp", "∓"),
+    ("leq", "≤"),
+    ("le", "≤"),
+    ("geq", "≥"),
+    ("ge", "≥"),
+    ("neq", "≠"),
+    ("ne", "≠"),
+    ("approx", "≈"),
+    ("sim", "∼"),
+    ("equiv", "≡"),
+    ("propto", "∝"),
+    ("infty", "∞"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("leftarrow", "←"),
+    ("leftrightarrow", "↔"),
+    ("Rightarrow", "⇒"),
+    ("partial", "∂"),
+    ("nabla", "∇"),
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("int", "∫"),
+    ("circ", "∘"),
+    ("prime", "′"),
+    ("in", "∈"),
+    ("subset", "⊂"),
+    ("cup", "∪"),
+    ("cap", "∩"),
+    ("emptyset", "∅"),
+    ("forall", "∀"),
+    ("exists", "∃"),
+    ("ell", "ℓ"),
+    ("hbar", "ℏ"),
+    ("ldots", "…"),
+    ("cdots", "⋯"),
+    ("%", "%"),
+    ("{", "{"),
+    ("}", "}"),
+    (",", " "),
+    (";", " "),
+    (" ", " "),
+];
+
+const SUPERSCRIPTS: (&str, &str) = ("0123456789+-=()ni", "⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻⁼⁽⁾ⁿⁱ");
+const SUBSCRIPTS: (&str, &str) = ("0123456789+-=()aeiox", "₀₁₂₃₄₅₆₇₈₉₊₋₌₍₎ₐₑᵢₒₓ");
+
+/// Converts LaTeX markup to Unicode text with Markdown emphasis.
+///
+/// Handles accents and special characters, dashes and quotes, font commands
+/// like `\emph` and `\textbf`, and inline math, which is converted to Unicode where possible.
+pub fn to_markdown(tex: &str) -> String {
+    if !tex.contains(&['\\', '{', '}', '$', '-', '~', '`', '\''][..]) {
+        return tex.to_owned();
+    }
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        pos: 0,
+    };
+    parser.parse_group(false)
+}
+
+/// Escapes plain text, so that [`to_markdown`] converts it back unchanged.
+///
+/// Used for values from formats other than BibTeX, which contain no LaTeX markup.
+pub fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.push_str("\\textbackslash{}"),
+            '~' => result.push_str("\\textasciitilde{}"),
+            '{' | '}' | '$' => {
+                result.push('\\');
+                result.push(c);
+            }
+            // Separate dashes and quotes that would be joined to a single character
+            '-' | '`' | '\'' if chars.peek() == Some(&c) => {
+                result.push(c);
+                result.push_str("{}");
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Normal,
+    Italic,
+    Bold,
+    Monospace,
+}
+
+impl Style {
+    fn apply(self, text: String) -> String {
+        let marker = match self {
+            Style::Normal => return text,
+            Style::Italic => "*",
+            Style::Bold => "**",
+            Style::Monospace => "`",
+        };
+        if text.trim().is_empty() {
+            return text;
+        }
+        // Keep surrounding whitespace outside of the markers
+        let start = text.len() - text.trim_start().len();
+        let end = text.trim_end().len();
+        format!(
+            "{}{}{}{}{}",
+            &text[..start],
+            marker,
+            &text[start..end],
+            marker,
+            &text[end..]
+        )
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Parses text up to the end, or up to the closing brace of a group.
+    fn parse_group(&mut self, in_group: bool) -> String {
+        let mut result = String::new();
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '}' if in_group => break,
+                '{' => {
+                    let group = self.parse_group(true);
+                    result.push_str(&group);
+                }
+                '}' => {}
+                '\\' => {
+                    let name = self.command_name();
+                    match switch_style(&name) {
+                        Some(switch) => {
+                            self.skip_space();
+                            // A switch applies to the rest of the group
+                            let rest = self.parse_group(in_group);
+                            result.push_str(&switch.apply(rest));
+                            return result;
+                        }
+                        None => result.push_str(&self.command(&name)),
+                    }
+                }
+                '$' => result.push_str(&self.math()),
+                '-' => {
+                    let mut count = 1;
+                    while self.peek() == Some('-') && count < 3 {
+                        self.pos += 1;
+                        count += 1;
+                    }
+                    result.push_str(match count {
+                        1 => "-",
+                        2 => "–",
+                        _ => "—",
+                    });
+                }
+                '~' => result.push('\u{a0}'),
+                '`' if self.peek() == Some('`') => {
+                    self.pos += 1;
+                    result.push('“');
+                }
+                '\'' if self.peek() == Some('\'') => {
+                    self.pos += 1;
+                    result.push('”');
+                }
+                c => result.push(c),
+            }
+        }
+
+        result
+    }
+
+    /// Reads a command name after a backslash: a sequence of letters, or a single other character.
+    fn command_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_alphabetic()) {
+            name.push(c);
+            self.pos += 1;
+        }
+        if name.is_empty() {
+            if let Some(c) = self.peek() {
+                name.push(c);
+                self.pos += 1;
+            }
+        }
+        name
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ') {
+            self.pos += 1;
+        }
+    }
+
+    /// Reads a command argument: a braced group, or a single character.
+    fn argument(&mut self) -> String {
+        self.skip_space();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.parse_group(true)
+            }
+            Some('\\') => {
+                self.pos += 1;
+                let name = self.command_name();
+                self.command(&name)
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Reads a braced argument verbatim, e.g. for URLs.
+    fn raw_argument(&mut self) -> String {
+        self.skip_space();
+        if self.peek() != Some('{') {
+            return String::new();
+        }
+        self.pos += 1;
+        let mut depth = 1;
+        let mut result = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            result.push(c);
+        }
+        result
+    }
+
+    fn command(&mut self, name: &str) -> String {
+        let is_word = name.chars().all(|c| c.is_ascii_alphabetic());
+        let after_name = self.pos;
+        let mut chars = name.chars();
+        if let (Some(accent), None) = (chars.next(), chars.next()) {
+            if let Some((_, bases, composed)) = ACCENTS.iter().find(|(a, _, _)| *a == accent) {
+                let arg = self.argument();
+                return compose(&arg, bases, composed);
+            }
+        }
+
+        let result = match name {
+            "emph" | "textit" | "textsl" => Style::Italic.apply(self.argument()),
+            "textbf" => Style::Bold.apply(self.argument()),
+            "texttt" => Style::Monospace.apply(self.argument()),
+            "textrm" | "textsf" | "textup" | "textnormal" | "textsc" | "mbox" | "text"
+            | "textmd" | "NoCaseChange" | "MakeUppercase" | "MakeLowercase" => self.argument(),
+            "url" => format!("<{}>", self.raw_argument()),
+            "href" => {
+                let url = self.raw_argument();
+                let text = self.argument();
+                format!("[{}]({})", text, url)
+            }
+            "&" | "%" | "$" | "#" | "_" | "{" | "}" => name.to_owned(),
+            "\\" | " " => " ".to_owned(),
+            "," | ";" | ":" => "\u{202f}".to_owned(),
+            "-" | "/" | "@" => String::new(),
+            _ => match SYMBOLS.iter().find(|(command, _)| *command == name) {
+                Some((_, symbol)) => symbol.to_string(),
+                // Drop unknown commands, but keep their arguments
+                None => String::new(),
+            },
+        };
+        // Spaces after command words without arguments are not part of the text
+        if is_word && self.pos == after_name {
+            self.skip_space();
+            if self.peek() == Some('{') && self.peek_at(1) == Some('}') {
+                self.pos += 2;
+            }
+        }
+        result
+    }
+
+    /// Converts inline math after an opening `$` to Unicode, or keeps it as Markdown math.
+    fn math(&mut self) -> String {
+        let start = self.pos;
+        let mut end = start;
+        while end < self.chars.len() && self.chars[end] != '$' {
+            end += if self.chars[end] == '\\' { 2 } else { 1 };
+        }
+        // Without a closing `$`, the opening one is literal text
+        if end >= self.chars.len() {
+            return "$".to_string();
+        }
+        self.pos = end + 1;
+        let math: String = self.chars[start..end].iter().collect();
+        math_to_unicode(&math).unwrap_or_else(|| format!("${}$", math))
+    }
+}
+
+fn switch_style(name: &str) -> Option<Style> {
+    match name {
+        "em" | "it" | "itshape" | "sl" | "slshape" => Some(Style::Italic),
+        "bf" | "bfseries" => Some(Style::Bold),
+        "tt" | "ttfamily" => Some(Style::Monospace),
+        "rm" | "sc" | "scshape" | "sf" | "normalfont" | "upshape" | "rmfamily" | "sffamily" => {
+            Some(Style::Normal)
+        }
+        _ => None,
+    }
+}
+
+/// Applies an accent to the first character of `arg`, falling back to the unaccented text.
+fn compose(arg: &str, bases: &str, composed: &str) -> String {
+    let mut chars = arg.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let accented = bases
+        .chars()
+        .zip(composed.chars())
+        .find(|(base, _)| *base == first)
+        .map_or(first, |(_, composed)| composed);
+    let mut result = accented.to_string();
+    result.extend(chars);
+    result
+}
+
+/// Converts simple math to Unicode. Returns `None` if anything can't be converted.
+fn math_to_unicode(math: &str) -> Option<String> {
+    let chars: Vec<char> = math.chars().collect();
+    let mut result = String::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        pos += 1;
+        match c {
+            '\\' => {
+                let start = pos;
+                while pos < chars.len() && chars[pos].is_ascii_alphabetic() {
+                    pos += 1;
+                }
+                if pos == start && pos < chars.len() {
+                    pos += 1;
+                }
+                let name: String = chars[start..pos].iter().collect();
+                match name.as_str() {
+                    "mathrm" | "mathit" | "mathbf" | "mathsf" | "text" | "mathnormal" => {
+                        let (group, end) = math_group(&chars, pos)?;
+                        result.push_str(&math_to_unicode(&group)?);
+                        pos = end;
+                    }
+                    _ => {
+                        let (_, symbol) = MATH_SYMBOLS.iter().find(|(n, _)| *n == name)?;
+                        result.push_str(symbol);
+                    }
+                }
+            }
+            '^' | '_' => {
+                let (group, end) = math_group(&chars, pos)?;
+                let (from, to) = if c == '^' { SUPERSCRIPTS } else { SUBSCRIPTS };
+                for g in math_to_unicode(&group)?.chars() {
+                    let idx = from.chars().position(|f| f == g)?;
+                    result.push(to.chars().nth(idx)?);
+                }
+                pos = end;
+            }
+            '{' | '}' => {}
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+/// Reads a braced group or a single character in math, returning it and the end position.
+fn math_group(chars: &[char], pos: usize) -> Option<(String, usize)> {
+    match chars.get(pos)? {
+        '{' => {
+            let mut depth = 0;
+            for (idx, c) in chars.iter().enumerate().skip(pos) {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some((chars[pos + 1..idx].iter().collect(), idx + 1));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            None
+        }
+        c => Some((c.to_string(), pos + 1)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, to_markdown};
+
+    #[test]
+    fn accents_and_symbols() {
+        assert_eq!(
+            to_markdown(r#"G{\"o}del, Escher, Bach"#),
+            "Gödel, Escher, Bach"
+        );
+        assert_eq!(
+            to_markdown(r#"Erd\H{o}s and Pet\"{o}fi"#),
+            "Erdős and Petöfi"
+        );
+        assert_eq!(to_markdown(r"\v{C}apek \c{c}a \'{e}t\'e"), "Čapek ça été");
+        assert_eq!(
+            to_markdown(r#"na\"{\i}ve Stra\ss{}e \AA{}ngstr\"om"#),
+            "naïve Straße Ångström"
+        );
+        assert_eq!(
+            to_markdown(r"Smith \& Sons, 50\% off"),
+            "Smith & Sons, 50% off"
+        );
+    }
+
+    #[test]
+    fn dashes_and_quotes() {
+        assert_eq!(to_markdown("pages 1--10 --- or not"), "pages 1–10 — or not");
+        assert_eq!(to_markdown("``quoted''"), "“quoted”");
+        assert_eq!(to_markdown("Fig.~3"), "Fig.\u{a0}3");
+    }
+
+    #[test]
+    fn emphasis() {
+        assert_eq!(
+            to_markdown(r"The \emph{Rust} \textbf{Book}"),
+            "The *Rust* **Book**"
+        );
+        assert_eq!(
+            to_markdown(r"A {\em very} {\bf bold} move"),
+            "A *very* **bold** move"
+        );
+        assert_eq!(
+            to_markdown(r"\texttt{cargo} and {Rust}"),
+            "`cargo` and Rust"
+        );
+    }
+
+    #[test]
+    fn math() {
+        assert_eq!(to_markdown(r"The $\alpha$-helix"), "The α-helix");
+        assert_eq!(to_markdown(r"$H_2O$ and $E = mc^2$"), "H₂O and E = mc²");
+        assert_eq!(to_markdown(r"$\frac{1}{2}$"), r"$\frac{1}{2}$");
+        assert_eq!(to_markdown(r"costs $5"), "costs $5");
+        assert_eq!(to_markdown(r"$x\"), "$x");
+        assert_eq!(to_markdown(r"$x\$"), "$x$");
+    }
+
+    #[test]
+    fn escape_plain_text() {
+        for text in &[
+            r"Costs $5 -- or ~3\4 {braces} ``quoted'' ---",
+            "Plain text, 1-2",
+            "$x\\",
+        ] {
+            assert_eq!(to_markdown(&escape(text)), *text);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::Entry;
+use std::fmt::Write;
+
+pub struct MiscFormatter {}
+
+impl EntryFormatter for MiscFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        if let Some(how) = super::format_field(item, "howpublished") {
+            write!(result, ". {}", how).unwrap();
+        }
+
+        if let Some(chunks) = item.publisher() {
+            write!(result, ". *{}*", super::format_chunks(&chunks, ", ")).unwrap();
+        }
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
@@ -1,35 +1,89 @@
 mod article;
 mod book;
+mod dataset;
 mod inbook;
 mod incollection;
+pub mod latex;
+mod manual;
+mod misc;
+mod online;
+mod patent;
+mod proceedings;
+mod report;
+mod software;
+mod thesis;
+mod unpublished;
 
-use crate::config::{CitationStyle, Config};
-use biblatex::{Chunk, ChunksExt, Date, DateValue, Entry, EntryType, Person};
+use crate::citation::{Citation, CitationItem, CitePosition, LocatorLabel};
+use crate::config::{Brackets, CitationStyle, Config, DateFormat, NameOrder, Tooltips};
+use crate::csl;
+use crate::disambiguate;
+use biblatex::{Chunk, ChunksExt, Date, DateValue, Datetime, Entry, EntryType, Person};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use std::fmt::Write;
 use std::ops::Range;
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+
+static MARKDOWN_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+static AUTOLINK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<([a-z]+:[^\s<>]*)>").unwrap());
 
 trait EntryFormatter: Send + Sync {
-    fn format(&self, write: &mut dyn Write, item: &Entry);
+    fn format(&self, write: &mut dyn Write, item: &Entry, config: &Config);
 }
 
 fn get_formatter(tp: &EntryType) -> Box<dyn EntryFormatter> {
-    match tp {
+    dedicated_formatter(tp).unwrap_or_else(|| Box::new(misc::MiscFormatter {}))
+}
+
+/// Whether an entry type is formatted by a formatter made for it, rather than the generic one.
+pub fn is_supported(tp: &EntryType) -> bool {
+    dedicated_formatter(tp).is_some()
+}
+
+fn dedicated_formatter(tp: &EntryType) -> Option<Box<dyn EntryFormatter>> {
+    Some(match tp {
         EntryType::Article => Box::new(article::ArticleFormatter {}),
         EntryType::Book => Box::new(book::BookFormatter {}),
         EntryType::InBook => Box::new(inbook::InBookFormatter {}),
         EntryType::InCollection | EntryType::InProceedings => {
             Box::new(incollection::InCollectionFormatter {})
         }
-        _ => Box::new(article::ArticleFormatter {}),
-    }
+        EntryType::Proceedings | EntryType::MvProceedings => {
+            Box::new(proceedings::ProceedingsFormatter {})
+        }
+        EntryType::TechReport | EntryType::Report => Box::new(report::ReportFormatter {}),
+        EntryType::Thesis | EntryType::PhdThesis | EntryType::MastersThesis => {
+            Box::new(thesis::ThesisFormatter {})
+        }
+        EntryType::Manual => Box::new(manual::ManualFormatter {}),
+        EntryType::Online => Box::new(online::OnlineFormatter {}),
+        EntryType::Unpublished => Box::new(unpublished::UnpublishedFormatter {}),
+        EntryType::Software => Box::new(software::SoftwareFormatter {}),
+        EntryType::Dataset => Box::new(dataset::DatasetFormatter {}),
+        EntryType::Patent => Box::new(patent::PatentFormatter {}),
+        EntryType::Misc => Box::new(misc::MiscFormatter {}),
+        _ => return None,
+    })
 }
 
-pub fn format_reference(item: &Entry, index: usize, config: &Config) -> String {
-    let formatter = get_formatter(&item.entry_type);
-
+/// Formats an entry of a reference list. Entries of a `local` list, for the references cited
+/// in that document, get anchors of their own, as the document can also contain the full list.
+pub fn format_reference(
+    item: &Entry,
+    index: usize,
+    local: Option<&Path>,
+    config: &Config,
+) -> String {
     let mut result = String::new();
     if config.link_refs {
-        write!(result, "{}", format_anchor(&item.key)).unwrap();
+        let anchor = match local {
+            Some(path) => local_anchor(path, &item.key),
+            None => key_to_anchor(&item.key),
+        };
+        write!(result, "{}", format_anchor(&anchor)).unwrap();
     }
     if config.citation_style == CitationStyle::Index {
         write!(result, "[{}] ", index).unwrap();
@@ -38,60 +92,351 @@ pub fn format_reference(item: &Entry, index: usize, config: &Config) -> String {
         write!(result, "[{}] ", item.key).unwrap();
     }
 
-    formatter.format(&mut result, item);
+    write!(result, "{}", format_reference_text(item, index, config)).unwrap();
 
     result
 }
 
+/// Formats a reference, without anchor, index and key.
+fn format_reference_text(item: &Entry, index: usize, config: &Config) -> String {
+    let mut result = String::new();
+    if let CitationStyle::Csl(style) = &config.citation_style {
+        write!(result, "{}", csl::render_bibliography(style, item, index)).unwrap();
+    } else {
+        get_formatter(&item.entry_type).format(&mut result, item, config);
+        write_identifiers(&mut result, item, config);
+    }
+    result
+}
+
 pub fn format_citation(
-    reference: &Entry,
-    index: usize,
+    citation: &Citation,
+    references: &[Option<(&Entry, usize, CitePosition)>],
     link_prefix: Option<&String>,
-    no_author: bool,
     config: &Config,
 ) -> String {
-    let anchor = key_to_anchor(&reference.key);
-    let prefix = link_prefix.cloned().unwrap_or_default();
+    if config.citation_style == CitationStyle::Index {
+        return format_numbers(citation, references, link_prefix, config);
+    }
+    let items = citation
+        .items
+        .iter()
+        .zip(references)
+        .map(|(item, reference)| format_citation_item(item, *reference, link_prefix, config))
+        .collect::<Vec<_>>();
 
-    if config.link_refs {
+    if citation.bracketed {
         match &config.citation_style {
-            CitationStyle::Index => {
-                format!("[{}]({}#{})", index, prefix, anchor)
+            CitationStyle::Csl(style) => csl::render_cluster(style, &items),
+            _ => format!("({})", items.join("; ")),
+        }
+    } else {
+        items.join("; ")
+    }
+}
+
+/// Formats a citation in the numbered style. Unless items have a prefix, locator or suffix,
+/// numbers are sorted and consecutive ones compressed to ranges like `[1–3, 5]`, if configured.
+fn format_numbers(
+    citation: &Citation,
+    references: &[Option<(&Entry, usize, CitePosition)>],
+    link_prefix: Option<&String>,
+    config: &Config,
+) -> String {
+    let numbers = &config.numbers;
+    let plain = citation
+        .items
+        .iter()
+        .all(|item| item.prefix.is_empty() && item.locator.is_none() && item.suffix.is_empty());
+
+    let text = if numbers.compress && plain {
+        let mut cited: Vec<_> = citation
+            .items
+            .iter()
+            .zip(references)
+            .filter_map(|(item, reference)| {
+                reference.map(|(entry, index, position)| (index, entry, item, position))
+            })
+            .collect();
+        cited.sort_by_key(|(index, _, _, _)| *index);
+        cited.dedup_by_key(|(index, _, _, _)| *index);
+        let format =
+            |(index, entry, item, position): &(usize, &Entry, &CitationItem, CitePosition)| {
+                format_citation_ref(entry, *index, link_prefix, item, *position, config)
+            };
+
+        let mut parts = vec![];
+        let mut start = 0;
+        while start < cited.len() {
+            let mut end = start;
+            while end + 1 < cited.len() && cited[end + 1].0 == cited[end].0 + 1 {
+                end += 1;
             }
-            CitationStyle::AuthorYear => {
-                let date = format_date(reference.date());
-                if no_author {
-                    format!("[{}]({}#{})", date, prefix, anchor)
-                } else {
-                    format!(
-                        "[{} {}]({}#{})",
-                        format_authors_citation(reference.author()),
-                        date,
-                        prefix,
-                        anchor
-                    )
-                }
+            // Two consecutive numbers are not worth a range
+            if end - start >= 2 {
+                parts.push(format!(
+                    "{}{}{}",
+                    format(&cited[start]),
+                    numbers.range_delimiter,
+                    format(&cited[end])
+                ));
+            } else {
+                parts.extend(cited[start..=end].iter().map(format));
             }
+            start = end + 1;
         }
+        parts.extend(
+            citation
+                .items
+                .iter()
+                .zip(references)
+                .filter(|(_, reference)| reference.is_none())
+                .map(|(item, _)| item.original()),
+        );
+        parts.join(&numbers.delimiter)
     } else {
-        match &config.citation_style {
-            CitationStyle::Index => {
-                format!("{}", index)
+        citation
+            .items
+            .iter()
+            .zip(references)
+            .map(|(item, reference)| format_citation_item(item, *reference, link_prefix, config))
+            .collect::<Vec<_>>()
+            .join(&numbers.citation_delimiter)
+    };
+
+    if citation.bracketed || numbers.brackets == Brackets::Superscript {
+        numbers.brackets.wrap(&text)
+    } else {
+        text
+    }
+}
+
+fn format_citation_item(
+    item: &CitationItem,
+    reference: Option<(&Entry, usize, CitePosition)>,
+    link_prefix: Option<&String>,
+    config: &Config,
+) -> String {
+    let mut result = String::new();
+    if !item.prefix.is_empty() {
+        write!(result, "{} ", item.prefix).unwrap();
+    }
+    match reference {
+        Some((entry, index, position)) => write!(
+            result,
+            "{}",
+            format_citation_ref(entry, index, link_prefix, item, position, config)
+        ),
+        None => write!(result, "{}", item.original()),
+    }
+    .unwrap();
+    // CSL styles render the locator themselves
+    let csl = matches!(config.citation_style, CitationStyle::Csl(_));
+    if let Some(locator) = item
+        .locator
+        .as_ref()
+        .filter(|_| !csl || reference.is_none())
+    {
+        write!(
+            result,
+            ", {} {}",
+            locator.label.term(locator.is_plural()),
+            locator.value
+        )
+        .unwrap();
+    }
+    write!(result, "{}", item.suffix).unwrap();
+    result
+}
+
+fn format_citation_ref(
+    reference: &Entry,
+    index: usize,
+    link_prefix: Option<&String>,
+    item: &CitationItem,
+    position: CitePosition,
+    config: &Config,
+) -> String {
+    let text = match &config.citation_style {
+        CitationStyle::Index => format!("{}", index),
+        CitationStyle::AuthorYear | CitationStyle::Notes => {
+            let date = format_year(reference, config);
+            if item.no_author {
+                date
+            } else {
+                format!("{} {}", format_authors_citation(reference, config), date)
+            }
+        }
+        CitationStyle::Csl(style) => csl::render_citation(style, reference, index, item, position),
+    };
+
+    let tooltip = match config.tooltips {
+        Tooltips::None => None,
+        _ => Some(plain_text(&format_reference_text(reference, index, config))),
+    };
+    // Markdown link titles require a link, so fall back to HTML without one
+    let html = config.tooltips == Tooltips::Html || !config.link_refs;
+
+    let text = if config.link_refs {
+        let title = match &tooltip {
+            Some(tooltip) if !html => format!(
+                " \"{}\"",
+                tooltip.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            _ => String::new(),
+        };
+        let anchor = key_to_anchor(&reference.key);
+        let prefix = link_prefix.cloned().unwrap_or_default();
+        format!("[{}]({}#{}{})", text, prefix, anchor, title)
+    } else {
+        text
+    };
+
+    match tooltip {
+        Some(tooltip) if html => format!(
+            "<span class=\"citation\" data-cites=\"{}\" title=\"{}\">{}</span>",
+            escape_html(&reference.key),
+            escape_html(&tooltip),
+            text
+        ),
+        _ => text,
+    }
+}
+
+/// How a reference is given in a footnote of the notes style.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteForm {
+    /// The full reference, on the first citation in a document
+    Full,
+    /// Authors and short title, on later citations
+    Short,
+    /// "Ibid.", when citing the same single reference as the previous footnote
+    Ibid,
+}
+
+/// Formats the text of the footnote for a citation in the notes style.
+pub fn format_note(
+    citation: &Citation,
+    references: &[Option<(&Entry, usize, NoteForm)>],
+    link_prefix: Option<&String>,
+    config: &Config,
+) -> String {
+    let items: Vec<_> = citation
+        .items
+        .iter()
+        .zip(references)
+        .map(|(item, reference)| {
+            let mut result = String::new();
+            if !item.prefix.is_empty() {
+                write!(result, "{} ", item.prefix).unwrap();
             }
-            CitationStyle::AuthorYear => {
-                let date = format_date(reference.date());
-                if no_author {
-                    date
+            match reference {
+                Some((entry, index, NoteForm::Full)) => write!(
+                    result,
+                    "{}",
+                    format_reference_text(entry, *index, config).trim_end_matches('.')
+                ),
+                Some((entry, _, NoteForm::Short)) => {
+                    write!(result, "{}", format_short_note(entry, link_prefix, config))
+                }
+                Some((_, _, NoteForm::Ibid)) => write!(result, "{}", config.terms.ibid),
+                None => write!(result, "{}", item.original()),
+            }
+            .unwrap();
+            if let Some(locator) = &item.locator {
+                // Page numbers are given without label, as in "Knuth, *Literate Programming*, 12"
+                if locator.label == LocatorLabel::Page {
+                    write!(result, ", {}", locator.value)
                 } else {
-                    format!("{} {}", format_authors_citation(reference.author()), date,)
+                    write!(
+                        result,
+                        ", {} {}",
+                        locator.label.term(locator.is_plural()),
+                        locator.value
+                    )
                 }
+                .unwrap();
             }
-        }
+            write!(result, "{}", item.suffix).unwrap();
+            result
+        })
+        .collect();
+
+    let mut note = items.join("; ");
+    if let Some(first) = note.chars().next() {
+        note.replace_range(..first.len_utf8(), &first.to_uppercase().to_string());
+    }
+    if !note.ends_with(&['.', '?', '!'][..]) {
+        note.push('.');
+    }
+    note
+}
+
+/// The short form of a reference in footnotes: authors and short title.
+fn format_short_note(item: &Entry, link_prefix: Option<&String>, config: &Config) -> String {
+    let title = item
+        .get("shorttitle")
+        .or_else(|| item.title())
+        .map(|title| format!("*{}*", format_chunk(title)));
+    let authors = format_authors_citation(item, config);
+    // Without authors and organization, the title is used in place of the authors
+    let text = match title {
+        Some(title) if title != authors => format!("{}, {}", authors, title),
+        _ => authors,
+    };
+    if config.link_refs {
+        let prefix = link_prefix.cloned().unwrap_or_default();
+        format!("[{}]({}#{})", text, prefix, key_to_anchor(&item.key))
+    } else {
+        text
     }
 }
 
-fn format_anchor(key: &str) -> String {
-    let anchor = key_to_anchor(key);
+/// The label of the footnote for the `number`th citation in a document, in the notes style.
+pub fn note_label(number: usize) -> String {
+    format!("[^cite-{}]", number)
+}
+
+/// Formats a citation in the notes style: the label of its footnote,
+/// preceded by the authors for citations without brackets, as in "Knuth[^cite-1]".
+pub fn format_note_citation(
+    citation: &Citation,
+    references: &[Option<(&Entry, usize, NoteForm)>],
+    number: usize,
+    config: &Config,
+) -> String {
+    let mut result = String::new();
+    if !citation.bracketed {
+        let authors: Vec<_> = citation
+            .items
+            .iter()
+            .zip(references)
+            .filter(|(item, _)| !item.no_author)
+            .filter_map(|(_, reference)| {
+                reference.map(|(entry, _, _)| format_authors_citation(entry, config))
+            })
+            .collect();
+        write!(result, "{}", authors.join("; ")).unwrap();
+    }
+    write!(result, "{}", note_label(number)).unwrap();
+    result
+}
+
+/// Removes Markdown emphasis and links from formatted text, for tooltips.
+fn plain_text(markdown: &str) -> String {
+    let text = MARKDOWN_LINK_REGEX.replace_all(markdown, "$1");
+    let text = AUTOLINK_REGEX.replace_all(&text, "$1");
+    text.replace("**", "").replace(&['*', '`'][..], "")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn format_anchor(anchor: &str) -> String {
     format!("<a name=\"{}\" id=\"{}\"></a>", anchor, anchor,)
 }
 
@@ -99,6 +444,25 @@ fn key_to_anchor(key: &str) -> String {
     format!("cite-ref-{}", key)
 }
 
+/// The anchor of a reference in the local list of a document, like `cite-ref-intro-Doe2020`.
+fn local_anchor(path: &Path, key: &str) -> String {
+    let document = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    format!("cite-ref-{}-{}", document, key)
+}
+
+/// The anchor of the `number`th citation of a key, for links back from the reference list.
+pub fn site_anchor(key: &str, number: usize) -> String {
+    format!("cite-{}-{}", key, number)
+}
+
+pub fn format_site_anchor(key: &str, number: usize) -> String {
+    let anchor = site_anchor(key, number);
+    format!("<a name=\"{}\" id=\"{}\"></a>", anchor, anchor)
+}
+
 fn format_pages(ranges: &[Range<u32>]) -> String {
     if ranges.is_empty() {
         "???".to_string()
@@ -107,78 +471,455 @@ fn format_pages(ranges: &[Range<u32>]) -> String {
     }
 }
 
-fn format_authors_opt(authors: Option<&Vec<Person>>) -> String {
-    if let Some(authors) = authors {
-        format_authors(authors)
+/// Writes the common start of a reference: creators, date and title.
+///
+/// Entries without author, editor or organization start with the title instead.
+fn write_header(result: &mut dyn Write, item: &Entry, config: &Config) {
+    let mut title = format_chunk_opt(item.title(), "Untitled");
+    if config.links.title {
+        if let Some(url) = doi_url(item).or_else(|| item.get("url").map(format_raw)) {
+            title = format!("[{}]({})", title, link_destination(&url));
+        }
+    }
+    let date = format_reference_date(item, config);
+    match format_creators_opt(item, config) {
+        Some(creators) => write!(result, "{} ({}): **{}**", creators, date, title),
+        None if item.title().is_some() => write!(result, "**{}** ({})", title, date),
+        None => write!(result, "Anonymous ({}): **{}**", date, title),
+    }
+    .unwrap();
+}
+
+/// Formats the creators of an entry for a reference list: the authors, or else the editors,
+/// or else the organization.
+pub fn format_creators_opt(item: &Entry, config: &Config) -> Option<String> {
+    if let Some(authors) = item.author().filter(|authors| !authors.is_empty()) {
+        Some(format_authors(&authors, config))
+    } else if let Some((editors, _)) = item.editors().first() {
+        Some(format_editors(editors, config))
     } else {
-        "Anonymous".to_string()
+        format_field(item, "organization")
     }
 }
 
-fn format_authors(authors: &[Person]) -> String {
-    let mut result = String::new();
-    for (idx, author) in authors.iter().enumerate() {
-        write!(result, "{}", author.name).unwrap();
-        if !author.given_name.is_empty() {
-            write!(result, " ").unwrap();
-            for part in author.given_name.split(' ') {
-                write!(result, "{}", &part[..1]).unwrap();
-            }
+/// Formats a list of editors, followed by "(ed.)" or "(eds.)".
+fn format_editors(editors: &[Person], config: &Config) -> String {
+    format!(
+        "{} ({})",
+        format_authors(editors, config),
+        if editors.len() == 1 { "ed." } else { "eds." }
+    )
+}
+
+/// The persons named in citations: the authors, or else the editors.
+pub fn creator_names(item: &Entry) -> Option<Vec<Person>> {
+    item.author()
+        .filter(|authors| !authors.is_empty())
+        .or_else(|| {
+            item.editors()
+                .into_iter()
+                .next()
+                .map(|(editors, _)| editors)
+        })
+        .filter(|names| !names.is_empty())
+}
+
+/// Whether a name is an organization rather than a person, like `{World Health Organization}`.
+pub fn is_corporate(person: &Person) -> bool {
+    person.given_name.is_empty()
+        && person.prefix.is_empty()
+        && person.name.trim().contains(char::is_whitespace)
+}
+
+/// Formats the names of a reference list, truncated with "et al." if configured.
+fn format_authors(authors: &[Person], config: &Config) -> String {
+    let names = &config.names;
+    let shown = match names.refs_et_al_min {
+        Some(min) if authors.len() >= min => names.refs_et_al_use_first.min(authors.len()),
+        _ => authors.len(),
+    };
+    let formatted: Vec<_> = authors[..shown]
+        .iter()
+        .map(|author| format_person(author, config))
+        .collect();
+    if shown < authors.len() {
+        format!(
+            "{} {}",
+            formatted.join(&names.separator),
+            config.terms.et_al
+        )
+    } else {
+        join_names(&formatted, &names.separator, names.and.as_deref())
+    }
+}
+
+/// Joins names with a separator, and a conjunction before the last name if given.
+fn join_names(names: &[String], separator: &str, and: Option<&str>) -> String {
+    match (names.split_last(), and) {
+        (Some((last, rest)), Some(and)) if !rest.is_empty() => {
+            format!("{} {} {}", rest.join(separator), and, last)
+        }
+        _ => names.join(separator),
+    }
+}
+
+/// Formats a person's name for a reference list.
+pub fn format_person(person: &Person, config: &Config) -> String {
+    let names = &config.names;
+    let family = latex::to_markdown(&person.name);
+    if is_corporate(person) {
+        return family;
+    }
+    let prefix = latex::to_markdown(&person.prefix);
+    let given = latex::to_markdown(&person.given_name);
+    let given = if names.initials {
+        format_initials(&given, config)
+    } else {
+        given
+    };
+
+    let prefix = if names.use_prefix {
+        prefix
+    } else {
+        String::new()
+    };
+
+    let mut parts = vec![];
+    match names.order {
+        NameOrder::FamilyFirst => {
+            parts.push(prefix.as_str());
+            parts.push(&family);
+            parts.push(&given);
         }
-        if idx < authors.len() - 1 {
-            write!(result, ", ").unwrap();
+        NameOrder::GivenFirst => {
+            parts.push(&given);
+            parts.push(&prefix);
+            parts.push(&family);
         }
     }
+    parts.retain(|part| !part.is_empty());
+    let mut result = parts.join(" ");
+    if !person.suffix.is_empty() {
+        write!(result, ", {}", latex::to_markdown(&person.suffix)).unwrap();
+    }
     result
 }
 
+/// Abbreviates given names to initials, e.g. `DE` or `D. E.` for Donald Ervin.
+fn format_initials(given: &str, config: &Config) -> String {
+    let names = &config.names;
+    initials(given, names.initials_period, names.initials_hyphen).join(if names.initials_period {
+        " "
+    } else {
+        ""
+    })
+}
+
+/// The initials of each given name, like `J.-P.` for Jean-Paul with period and hyphen.
+///
+/// Names are separated by whitespace or periods, so `D.E.` gives the same initials as `Donald Ervin`.
+pub fn initials(given: &str, period: bool, hyphen: bool) -> Vec<String> {
+    let period = if period { "." } else { "" };
+    given
+        .split(|c: char| c.is_whitespace() || c == '.')
+        .filter_map(|part| {
+            let parts: Vec<_> = if hyphen {
+                part.split('-').filter_map(initial).collect()
+            } else {
+                initial(part).into_iter().collect()
+            };
+            if parts.is_empty() {
+                None
+            } else {
+                Some(
+                    parts
+                        .iter()
+                        .map(|initial| format!("{}{}", initial, period))
+                        .collect::<Vec<_>>()
+                        .join("-"),
+                )
+            }
+        })
+        .collect()
+}
+
+/// The first letter of a name, as a grapheme cluster like `É` or `E` with combining accent.
+pub fn initial(name: &str) -> Option<&str> {
+    name.trim_start_matches(|c: char| !c.is_alphanumeric())
+        .graphemes(true)
+        .next()
+}
+
 fn format_chunk_opt(chunks: Option<&[Chunk]>, alternative: &str) -> String {
     chunks
-        .map(|chunks| chunks.format_verbatim())
+        .map(format_chunk)
         .unwrap_or_else(|| alternative.to_string())
 }
 
-fn format_chunk(chunks: &[Chunk]) -> String {
+fn format_field(item: &Entry, field: &str) -> Option<String> {
+    item.get(field).map(format_chunk)
+}
+
+/// Writes the `note`, `url` and `urldate` fields, if present.
+///
+/// The URL is left out if it is written with the other identifiers, see [`write_identifiers`].
+fn write_note_url(result: &mut dyn Write, item: &Entry, config: &Config) {
+    if let Some(note) = format_field(item, "note") {
+        // The entry formatters end with a period of their own
+        write!(result, ". {}", note.trim_end_matches('.')).unwrap();
+    }
+
+    if !config.links.url {
+        write_url(result, ". ", item);
+    }
+}
+
+fn write_url(result: &mut dyn Write, separator: &str, item: &Entry) {
+    if let Some(url) = item.get("url").map(format_raw) {
+        write!(result, "{}<{}>", separator, url).unwrap();
+        if let Some(date) = format_field(item, "urldate") {
+            write!(result, " (accessed {})", date).unwrap();
+        }
+    }
+}
+
+/// Writes the ISBN, ISSN, DOI, arXiv ID and URL of an entry, as enabled by option `links`.
+fn write_identifiers(result: &mut dyn Write, item: &Entry, config: &Config) {
+    let links = &config.links;
+    if links.isbn {
+        if let Some(isbn) = item.get("isbn").map(format_raw) {
+            write!(result, " ISBN {}.", isbn).unwrap();
+        }
+    }
+    if links.issn {
+        if let Some(issn) = item.get("issn").map(format_raw) {
+            write!(result, " ISSN {}.", issn).unwrap();
+        }
+    }
+    if links.doi {
+        if let Some(doi) = doi(item) {
+            write!(
+                result,
+                " [doi:{}]({}).",
+                doi,
+                link_destination(&format!("https://doi.org/{}", doi))
+            )
+            .unwrap();
+        }
+    }
+    if links.arxiv {
+        if let Some(id) = arxiv_id(item) {
+            write!(
+                result,
+                " [arXiv:{}]({}).",
+                id,
+                link_destination(&format!("https://arxiv.org/abs/{}", id))
+            )
+            .unwrap();
+        }
+    }
+    if links.url && item.get("url").is_some() {
+        write_url(result, " ", item);
+        write!(result, ".").unwrap();
+    }
+}
+
+/// The DOI of an entry, without any resolver prefix.
+fn doi(item: &Entry) -> Option<String> {
+    let doi = item.get("doi").map(format_raw)?;
+    let doi = doi.trim();
+    let doi = [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "http://dx.doi.org/",
+        "doi:",
+    ]
+    .iter()
+    .find_map(|prefix| doi.strip_prefix(prefix))
+    .unwrap_or(doi);
+    Some(doi.to_owned()).filter(|doi| !doi.is_empty())
+}
+
+fn doi_url(item: &Entry) -> Option<String> {
+    doi(item).map(|doi| format!("https://doi.org/{}", doi))
+}
+
+/// The arXiv identifier from field `eprint`, if `eprinttype` or `archiveprefix` is arXiv.
+fn arxiv_id(item: &Entry) -> Option<String> {
+    // Field names keep their case from the source, like `archivePrefix`
+    let is_arxiv = item.fields.iter().any(|(field, chunks)| {
+        ["eprinttype", "archiveprefix"]
+            .iter()
+            .any(|name| field.eq_ignore_ascii_case(name))
+            && format_raw(chunks).trim().eq_ignore_ascii_case("arxiv")
+    });
+    if !is_arxiv {
+        return None;
+    }
+    let id = item.get("eprint").map(format_raw)?;
+    let id = id.trim();
+    let id = id.strip_prefix("arXiv:").unwrap_or(id);
+    Some(id.to_owned()).filter(|id| !id.is_empty())
+}
+
+/// Escapes characters that are not allowed in Markdown link destinations.
+fn link_destination(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+/// Formats a field value, converting LaTeX markup to Unicode and Markdown.
+pub fn format_chunk(chunks: &[Chunk]) -> String {
+    latex::to_markdown(&chunks.format_verbatim())
+}
+
+/// Formats a field value without conversion, e.g. for URLs and DOIs.
+pub fn format_raw(chunks: &[Chunk]) -> String {
     chunks.format_verbatim()
 }
 
-fn format_chunks(chunks: &[Vec<Chunk>], sep: &str) -> String {
+pub fn format_chunks(chunks: &[Vec<Chunk>], sep: &str) -> String {
     chunks
         .iter()
-        .map(|chunk| chunk.format_verbatim())
+        .map(|chunk| format_chunk(chunk))
         .collect::<Vec<_>>()
         .join(sep)
 }
 
-fn format_authors_citation(authors: Option<Vec<Person>>) -> String {
-    let mut result = String::new();
-    if let Some(authors) = authors {
-        match authors.len() {
-            1 => write!(result, "{}", authors[0].name).unwrap(),
-            2 => write!(result, "{} & {}", authors[0].name, authors[1].name).unwrap(),
-            _ => write!(result, "{} et al.", authors[0].name).unwrap(),
+/// Formats the authors for an author-year citation,
+/// with given names or initials where required for disambiguation.
+pub fn format_authors_citation(item: &Entry, config: &Config) -> String {
+    let names = &config.names;
+    match creator_names(item) {
+        Some(authors) => {
+            let levels = disambiguate::name_levels(item);
+            let formatted: Vec<_> = disambiguate::cited_authors(&authors, config)
+                .iter()
+                .enumerate()
+                .map(|(idx, author)| {
+                    disambiguate::format_name(author, levels.get(idx).copied().unwrap_or(0), config)
+                })
+                .collect();
+            if formatted.len() < authors.len() {
+                format!(
+                    "{} {}",
+                    formatted.join(&names.separator),
+                    config.terms.et_al
+                )
+            } else if formatted.len() == 2 {
+                format!("{} {} {}", formatted[0], names.citation_and, formatted[1])
+            } else {
+                join_names(&formatted, &names.separator, Some(&names.citation_and))
+            }
         }
-    } else {
-        write!(result, "Anonymous").unwrap();
+        None => format_field(item, "organization")
+            .or_else(|| {
+                item.get("shorttitle")
+                    .or_else(|| item.title())
+                    .map(|title| format!("*{}*", format_chunk(title)))
+            })
+            .unwrap_or_else(|| "Anonymous".to_string()),
+    }
+}
+
+/// Formats the year of an entry for citations, with the year suffix assigned by disambiguation.
+pub fn format_year(item: &Entry, config: &Config) -> String {
+    format!(
+        "{}{}",
+        format_date(item, DateFormat::Year, config),
+        disambiguate::year_suffix(item)
+    )
+}
+
+/// Formats the date of an entry for reference lists, with the original date if given.
+pub fn format_reference_date(item: &Entry, config: &Config) -> String {
+    let date = format_date(item, config.date_format, config);
+    let suffix = disambiguate::year_suffix(item);
+    // The year suffix follows the year, also in dates with months like `2020b, March`
+    let mut result = match date.find(", ") {
+        Some(idx) if date[..idx].chars().all(|c| c.is_ascii_digit()) => {
+            format!("{}{}{}", &date[..idx], suffix, &date[idx..])
+        }
+        _ => format!("{}{}", date, suffix),
+    };
+    if let Some(date) = item.get_as::<Date>("origdate") {
+        write!(
+            result,
+            " [{}]",
+            format_date_value(&date, config.date_format)
+        )
+        .unwrap();
     }
     result
 }
 
-pub fn format_date(date: Option<Date>) -> String {
-    if let Some(date) = date {
-        if let DateValue::At(time) = date.value {
-            format!("{}", time.year)
-        } else {
-            "????".to_owned()
+/// Formats the date of an entry, or a term like "n.d." or "forthcoming" if it has none.
+pub fn format_date(item: &Entry, format: DateFormat, config: &Config) -> String {
+    match item.date() {
+        Some(date) => format_date_value(&date, format),
+        None => match format_field(item, "pubstate").as_deref() {
+            Some("forthcoming") => config.terms.forthcoming.clone(),
+            Some("inpress") | Some("in press") => config.terms.in_press.clone(),
+            _ => config.terms.no_date.clone(),
+        },
+    }
+}
+
+/// Formats a date, or a range of dates.
+pub fn format_date_value(date: &Date, format: DateFormat) -> String {
+    match &date.value {
+        DateValue::At(time) => format_datetime(time, format),
+        DateValue::After(time) => format!("{}–", format_datetime(time, format)),
+        DateValue::Before(time) => format!("–{}", format_datetime(time, format)),
+        DateValue::Between(start, end) => {
+            let (start, end) = (format_datetime(start, format), format_datetime(end, format));
+            if start == end {
+                start
+            } else {
+                format!("{}–{}", start, end)
+            }
         }
-    } else {
-        "????".to_owned()
     }
 }
 
+fn format_datetime(time: &Datetime, format: DateFormat) -> String {
+    let month = time
+        .month
+        .filter(|_| format != DateFormat::Year)
+        .and_then(|month| MONTHS.get(month as usize));
+    let day = time.day.filter(|_| format == DateFormat::Full);
+    match (month, day) {
+        (Some(month), Some(day)) => format!("{}, {} {}", time.year, month, day + 1),
+        (Some(month), None) => format!("{}, {}", time.year, month),
+        _ => time.year.to_string(),
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
 #[cfg(test)]
 mod test {
-    use crate::config::{CitationStyle, Config};
+    use crate::citation::{parse_citations, CitePosition};
+    use crate::config::{
+        Brackets, CitationStyle, Config, DateFormat, Links, NameOrder, Names, Numbers, Tooltips,
+    };
     use biblatex::Bibliography;
 
     const TEST_BIB: &str = r#"
@@ -192,26 +933,522 @@ mod test {
 "#;
 
     #[test]
-    fn format_citation() {
+    fn format_citation_ref() {
         let config = Config {
-            bib_file: "".to_string(),
             citation_style: CitationStyle::AuthorYear,
-            refs_file: None,
-            placeholder: "[[_REFS_]]".to_string(),
-            render_key: true,
-            link_refs: true,
+            ..Config::default()
         };
 
         let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let citations = parse_citations("@Klabnik2018 -@Klabnik2018");
+        let entry = bib.get("Klabnik2018").unwrap();
 
         assert_eq!(
-            super::format_citation(bib.get("Klabnik2018").unwrap(), 1, None, false, &config),
+            super::format_citation_ref(
+                entry,
+                1,
+                None,
+                &citations[0].1.items[0],
+                CitePosition::First,
+                &config
+            ),
             "[Klabnik & Nichols 2018](#cite-ref-Klabnik2018)"
         );
 
         assert_eq!(
-            super::format_citation(bib.get("Klabnik2018").unwrap(), 1, None, true, &config),
+            super::format_citation_ref(
+                entry,
+                1,
+                None,
+                &citations[1].1.items[0],
+                CitePosition::First,
+                &config
+            ),
             "[2018](#cite-ref-Klabnik2018)"
         );
     }
+
+    #[test]
+    fn format_citation_group() {
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            ..Config::default()
+        };
+
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let (_, citation) = parse_citations("[see @Klabnik2018, pp. 33-35; also @Unknown]")
+            .pop()
+            .unwrap();
+        let references = vec![
+            Some((bib.get("Klabnik2018").unwrap(), 1, CitePosition::First)),
+            None,
+        ];
+
+        assert_eq!(
+            super::format_citation(&citation, &references, None, &config),
+            "(see [Klabnik & Nichols 2018](#cite-ref-Klabnik2018), pp. 33-35; also @Unknown)"
+        );
+    }
+
+    #[test]
+    fn format_initials() {
+        let config = Config::default();
+        let initials = |given: &str| super::format_initials(given, &config);
+        assert_eq!(initials("Donald Ervin"), "DE");
+        assert_eq!(initials("D.E."), "DE");
+        assert_eq!(initials("Élodie"), "É");
+        assert_eq!(initials("E\u{301}lodie"), "E\u{301}");
+        assert_eq!(initials("Øystein"), "Ø");
+        assert_eq!(initials("Ólafur Ragnar"), "ÓR");
+        assert_eq!(initials("Şükrü"), "Ş");
+        assert_eq!(initials("Владимир Ильич"), "ВИ");
+        assert_eq!(initials("泽东"), "泽");
+        assert_eq!(initials("Jean-Paul"), "J-P");
+        assert_eq!(initials("Anna  Maria "), "AM");
+        assert_eq!(initials("-"), "");
+        assert_eq!(initials("(Bob)"), "B");
+
+        let config = Config {
+            names: Names {
+                initials_period: true,
+                ..Config::default().names
+            },
+            ..Config::default()
+        };
+        assert_eq!(super::format_initials("Jean-Paul", &config), "J.-P.");
+        assert_eq!(super::format_initials("Øystein Ólafur", &config), "Ø. Ó.");
+
+        let config = Config {
+            names: Names {
+                initials_hyphen: false,
+                ..Config::default().names
+            },
+            ..Config::default()
+        };
+        assert_eq!(super::format_initials("Jean-Paul", &config), "J");
+    }
+
+    #[test]
+    fn format_names() {
+        let bib = Bibliography::parse(
+            r#"
+@book{Waals1873,
+    author = {van der Waals, Johannes Diderik and King, Jr., Martin Luther and Sartre, Jean-Paul},
+    title = {Names},
+    year = {1873},
+}
+"#,
+        )
+        .unwrap();
+        let entry = bib.get("Waals1873").unwrap();
+        let authors = entry.author().unwrap();
+
+        let mut config = Config::default();
+        assert_eq!(
+            super::format_authors(&authors, &config),
+            "Waals JD, King ML, Jr., Sartre J-P"
+        );
+        assert_eq!(
+            super::format_authors_citation(entry, &config),
+            "Waals et al."
+        );
+
+        config.names.use_prefix = true;
+        assert_eq!(
+            super::format_authors(&authors, &config),
+            "van der Waals JD, King ML, Jr., Sartre J-P"
+        );
+        assert_eq!(
+            super::format_authors_citation(entry, &config),
+            "van der Waals et al."
+        );
+
+        let mut config = Config {
+            names: Names {
+                order: NameOrder::GivenFirst,
+                initials_period: true,
+                and: Some("and".to_string()),
+                et_al_min: 4,
+                citation_and: "and".to_string(),
+                ..Config::default().names
+            },
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_authors(&authors, &config),
+            "J. D. Waals, M. L. King, Jr. and J.-P. Sartre"
+        );
+        assert_eq!(
+            super::format_authors_citation(entry, &config),
+            "Waals, King and Sartre"
+        );
+
+        config.names.use_prefix = true;
+        assert_eq!(
+            super::format_authors(&authors, &config),
+            "J. D. van der Waals, M. L. King, Jr. and J.-P. Sartre"
+        );
+        assert_eq!(
+            super::format_authors_citation(entry, &config),
+            "van der Waals, King and Sartre"
+        );
+
+        let config = Config {
+            names: Names {
+                initials: false,
+                refs_et_al_min: Some(3),
+                refs_et_al_use_first: 2,
+                ..Config::default().names
+            },
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_authors(&authors, &config),
+            "Waals Johannes Diderik, King Martin Luther, Jr. et al."
+        );
+    }
+
+    #[test]
+    fn format_creators() {
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            render_key: false,
+            link_refs: false,
+            ..Config::default()
+        };
+        let bib = Bibliography::parse(
+            r#"
+@report{WHO2020,
+    author = {{World Health Organization}},
+    title = {World Health Statistics},
+    year = {2020},
+}
+@collection{Doe2019,
+    editor = {Doe, John and Roe, Jane},
+    title = {Collected Papers},
+    year = {2019},
+}
+@manual{Rust2021,
+    organization = {The Rust Team},
+    title = {The Cargo Book},
+    year = {2021},
+}
+@misc{Untitled2022,
+    title = {Anonymous Pamphlet},
+    year = {2022},
+}
+@online{RustWeb2021,
+    organization = {Rust Foundation},
+    title = {Rust Website},
+    year = {2021},
+}
+@software{Cargo2021,
+    organization = {Rust Team},
+    title = {Cargo},
+    year = {2021},
+}
+"#,
+        )
+        .unwrap();
+
+        let format = |key: &str| {
+            let entry = bib.get(key).unwrap();
+            (
+                super::format_authors_citation(entry, &config),
+                super::format_reference(entry, 1, None, &config),
+            )
+        };
+        assert_eq!(
+            format("WHO2020"),
+            (
+                "World Health Organization".to_string(),
+                "World Health Organization (2020): **World Health Statistics**. Report."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            format("Doe2019"),
+            (
+                "Doe & Roe".to_string(),
+                "Doe J, Roe J (eds.) (2019): **Collected Papers**.".to_string()
+            )
+        );
+        assert_eq!(
+            format("Rust2021"),
+            (
+                "The Rust Team".to_string(),
+                "The Rust Team (2021): **The Cargo Book**.".to_string()
+            )
+        );
+        assert_eq!(
+            format("Untitled2022"),
+            (
+                "*Anonymous Pamphlet*".to_string(),
+                "**Anonymous Pamphlet** (2022).".to_string()
+            )
+        );
+        assert_eq!(
+            format("RustWeb2021").1,
+            "Rust Foundation (2021): **Rust Website**."
+        );
+        assert_eq!(
+            format("Cargo2021").1,
+            "Rust Team (2021): **Cargo** [Computer software]."
+        );
+    }
+
+    #[test]
+    fn format_identifiers() {
+        let bib = Bibliography::parse(
+            r#"
+@article{Doe2021,
+    author = {Doe, John},
+    title = {Deep Things},
+    journal = {Journal},
+    year = {2021},
+    doi = {https://doi.org/10.1000/xyz(1)},
+    eprint = {2101.00001},
+    archivePrefix = {arXiv},
+    issn = {1234-5678},
+    url = {https://example.com},
+}
+"#,
+        )
+        .unwrap();
+        let entry = bib.get("Doe2021").unwrap();
+
+        let config = Config {
+            render_key: false,
+            link_refs: false,
+            links: Links {
+                doi: true,
+                arxiv: true,
+                isbn: true,
+                issn: true,
+                url: true,
+                title: false,
+            },
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_reference(entry, 1, None, &config),
+            "Doe J (2021): **Deep Things**. *Journal*. ISSN 1234-5678. \
+             [doi:10.1000/xyz(1)](https://doi.org/10.1000/xyz%281%29). \
+             [arXiv:2101.00001](https://arxiv.org/abs/2101.00001). <https://example.com>."
+        );
+
+        let config = Config {
+            render_key: false,
+            link_refs: false,
+            links: Links {
+                title: true,
+                ..Config::default().links
+            },
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_reference(entry, 1, None, &config),
+            "Doe J (2021): **[Deep Things](https://doi.org/10.1000/xyz%281%29)**. *Journal*."
+        );
+    }
+
+    #[test]
+    fn format_tooltips() {
+        let bib = Bibliography::parse(
+            r#"
+@book{Doe2020,
+    author = {Doe, John},
+    title = {The "Best" \emph{Book}},
+    year = {2020},
+    publisher = {Press & Co},
+}
+"#,
+        )
+        .unwrap();
+        let entry = bib.get("Doe2020").unwrap();
+        let citations = parse_citations("@Doe2020");
+        let item = &citations[0].1.items[0];
+
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            tooltips: Tooltips::Title,
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_citation_ref(entry, 1, None, item, CitePosition::First, &config),
+            r#"[Doe 2020](#cite-ref-Doe2020 "Doe J (2020): The \"Best\" Book. Press & Co.")"#
+        );
+
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            tooltips: Tooltips::Html,
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_citation_ref(entry, 1, None, item, CitePosition::First, &config),
+            "<span class=\"citation\" data-cites=\"Doe2020\" \
+             title=\"Doe J (2020): The &quot;Best&quot; Book. Press &amp; Co.\">\
+             [Doe 2020](#cite-ref-Doe2020)</span>"
+        );
+    }
+
+    #[test]
+    fn format_numbers() {
+        let bib =
+            Bibliography::parse("@book{Doe2020, author = {Doe, John}, year = {2020}}").unwrap();
+        let entry = bib.get("Doe2020").unwrap();
+        let references: Vec<_> = [Some(5), Some(2), Some(1), Some(3), None, Some(7), Some(8)]
+            .iter()
+            .map(|index| index.map(|index| (entry, index, CitePosition::First)))
+            .collect();
+        let citations = parse_citations("[@e; @b; @a; @c; @x; @g; @h]");
+        let citation = &citations[0].1;
+
+        let mut config = Config {
+            citation_style: CitationStyle::Index,
+            link_refs: false,
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_citation(citation, &references, None, &config),
+            "[1–3, 5, 7, 8, @x]"
+        );
+
+        config.numbers.compress = false;
+        assert_eq!(
+            super::format_citation(citation, &references, None, &config),
+            "[5; 2; 1; 3; @x; 7; 8]"
+        );
+
+        let config = Config {
+            citation_style: CitationStyle::Index,
+            numbers: Numbers {
+                brackets: Brackets::Superscript,
+                compress: true,
+                delimiter: ",".to_string(),
+                citation_delimiter: "; ".to_string(),
+                range_delimiter: "-".to_string(),
+                sorted: false,
+            },
+            ..Config::default()
+        };
+        assert_eq!(
+            super::format_citation(citation, &references[..3], None, &config),
+            "<sup>[1](#cite-ref-Doe2020),[2](#cite-ref-Doe2020),[5](#cite-ref-Doe2020)</sup>"
+        );
+
+        let config = Config {
+            citation_style: CitationStyle::Index,
+            link_refs: false,
+            ..Config::default()
+        };
+        let citations = parse_citations("[@a, p. 3; @b]");
+        assert_eq!(
+            super::format_citation(&citations[0].1, &references[1..3], None, &config),
+            "[2, p. 3; 1]"
+        );
+    }
+
+    #[test]
+    fn format_reference_types() {
+        let config = Config {
+            citation_style: CitationStyle::AuthorYear,
+            render_key: false,
+            link_refs: false,
+            ..Config::default()
+        };
+
+        let bib = Bibliography::parse(
+            r#"
+@phdthesis{Doe2019,
+    author = {Doe, John},
+    title = {A Thesis},
+    school = {University of Somewhere},
+    year = {2019},
+}
+@techreport{Roe2020,
+    author = {Roe, Jane},
+    title = {A Report},
+    institution = {Some Institute},
+    number = {42},
+    year = {2020},
+}
+@online{Web2021,
+    author = {Doe, John},
+    title = {A Website},
+    url = {https://example.com},
+    urldate = {2021-03-04},
+    year = {2021},
+}
+@misc{Misc2021,
+    author = {Doe, John},
+    title = {Something},
+    howpublished = {Poster},
+    note = {Presented at a conference},
+    year = {2021},
+}
+@misc{Talk2022,
+    author = {Doe, John},
+    title = {A Talk},
+    note = {Personal communication.},
+    year = {2022},
+}
+"#,
+        )
+        .unwrap();
+
+        let format = |key| super::format_reference(bib.get(key).unwrap(), 1, None, &config);
+
+        assert_eq!(
+            format("Doe2019"),
+            "Doe J (2019): **A Thesis**. PhD thesis. *University of Somewhere*."
+        );
+        assert_eq!(
+            format("Roe2020"),
+            "Roe J (2020): **A Report**. Technical report 42. *Some Institute*."
+        );
+        assert_eq!(
+            format("Web2021"),
+            "Doe J (2021): **A Website**. <https://example.com> (accessed 2021-03-04)."
+        );
+        assert_eq!(
+            format("Misc2021"),
+            "Doe J (2021): **Something**. Poster. Presented at a conference."
+        );
+        assert_eq!(
+            format("Talk2022"),
+            "Doe J (2022): **A Talk**. Personal communication."
+        );
+    }
+
+    #[test]
+    fn format_dates() {
+        let bib = Bibliography::parse(
+            r#"
+@book{Range, date = {2018/2020}}
+@book{Open, date = {2018/}}
+@book{Month, date = {2018-05-03}, origdate = {1970}}
+@book{NoDate, title = {No Date}}
+@book{Forthcoming, pubstate = {forthcoming}}
+@book{Suffix, date = {2020-03-12}, extradate = {2}}
+"#,
+        )
+        .unwrap();
+
+        let config = Config {
+            date_format: DateFormat::Full,
+            ..Config::default()
+        };
+        let year = |key: &str| super::format_year(bib.get(key).unwrap(), &config);
+        let reference = |key: &str| super::format_reference_date(bib.get(key).unwrap(), &config);
+
+        assert_eq!(year("Range"), "2018–2020");
+        assert_eq!(year("Open"), "2018–");
+        assert_eq!(year("Month"), "2018");
+        assert_eq!(reference("Month"), "2018, May 3 [1970]");
+        assert_eq!(year("NoDate"), "n.d.");
+        assert_eq!(year("Forthcoming"), "forthcoming");
+        assert_eq!(year("Suffix"), "2020b");
+        assert_eq!(reference("Suffix"), "2020b, March 12");
+    }
 }
@@ -0,0 +1,31 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::Entry;
+use std::fmt::Write;
+
+pub struct PatentFormatter {}
+
+impl EntryFormatter for PatentFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        write!(
+            result,
+            ". {}",
+            super::format_field(item, "type").unwrap_or_else(|| "Patent".to_string())
+        )
+        .unwrap();
+
+        if let Some(number) = item.number() {
+            write!(result, " {}", super::format_chunk(number)).unwrap();
+        }
+
+        if let Some(holder) = super::format_field(item, "holder") {
+            write!(result, ". Assignee: {}", holder).unwrap();
+        }
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::Entry;
+use std::fmt::Write;
+
+pub struct ProceedingsFormatter {}
+
+impl EntryFormatter for ProceedingsFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        if let Some(volume) = item.volume() {
+            write!(result, ", Vol. {}", volume).unwrap();
+        }
+
+        if let Some(chunks) = item.publisher() {
+            write!(result, ". *{}*", super::format_chunks(&chunks, ", ")).unwrap();
+        } else if let Some(organization) = super::format_field(item, "organization") {
+            write!(result, ". *{}*", organization).unwrap();
+        }
+
+        if let Some(chunks) = item.address() {
+            write!(result, ", {}", super::format_chunk(chunks)).unwrap();
+        }
+
+        write!(result, ".").unwrap();
+    }
+}
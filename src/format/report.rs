@@ -0,0 +1,40 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::{Entry, EntryType};
+use std::fmt::Write;
+
+pub struct ReportFormatter {}
+
+impl EntryFormatter for ReportFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        let default_type = if item.entry_type == EntryType::TechReport {
+            "Technical report"
+        } else {
+            "Report"
+        };
+        write!(
+            result,
+            ". {}",
+            super::format_field(item, "type").unwrap_or_else(|| default_type.to_string())
+        )
+        .unwrap();
+
+        if let Some(number) = item.number() {
+            write!(result, " {}", super::format_chunk(number)).unwrap();
+        }
+
+        if let Some(institution) = super::format_field(item, "institution") {
+            write!(result, ". *{}*", institution).unwrap();
+        }
+
+        if let Some(chunks) = item.address() {
+            write!(result, ", {}", super::format_chunk(chunks)).unwrap();
+        }
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
@@ -0,0 +1,31 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::Entry;
+use std::fmt::Write;
+
+pub struct SoftwareFormatter {}
+
+impl EntryFormatter for SoftwareFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        if let Some(version) = super::format_field(item, "version") {
+            write!(result, ". Version {}", version).unwrap();
+        }
+
+        write!(result, " [Computer software]").unwrap();
+
+        // Without authors or editors, the organization is already shown as the creator
+        let organization = super::format_field(item, "organization")
+            .filter(|_| super::creator_names(item).is_some());
+        if let Some(chunks) = item.publisher() {
+            write!(result, ". *{}*", super::format_chunks(&chunks, ", ")).unwrap();
+        } else if let Some(organization) = organization {
+            write!(result, ". *{}*", organization).unwrap();
+        }
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
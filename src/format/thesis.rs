@@ -0,0 +1,38 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::{Entry, EntryType};
+use std::fmt::Write;
+
+pub struct ThesisFormatter {}
+
+impl EntryFormatter for ThesisFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        let default_type = match item.entry_type {
+            EntryType::PhdThesis => "PhD thesis",
+            EntryType::MastersThesis => "Master's thesis",
+            _ => "Thesis",
+        };
+        write!(
+            result,
+            ". {}",
+            super::format_field(item, "type").unwrap_or_else(|| default_type.to_string())
+        )
+        .unwrap();
+
+        if let Some(school) =
+            super::format_field(item, "school").or_else(|| super::format_field(item, "institution"))
+        {
+            write!(result, ". *{}*", school).unwrap();
+        }
+
+        if let Some(chunks) = item.address() {
+            write!(result, ", {}", super::format_chunk(chunks)).unwrap();
+        }
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
@@ -0,0 +1,24 @@
+use crate::config::Config;
+use crate::format::EntryFormatter;
+use biblatex::Entry;
+use std::fmt::Write;
+
+pub struct UnpublishedFormatter {}
+
+impl EntryFormatter for UnpublishedFormatter {
+    fn format(&self, result: &mut dyn Write, item: &Entry, config: &Config) {
+        super::write_header(result, item, config);
+
+        write!(
+            result,
+            ". {}",
+            super::format_field(item, "howpublished")
+                .unwrap_or_else(|| "Unpublished manuscript".to_string())
+        )
+        .unwrap();
+
+        super::write_note_url(result, item, config);
+
+        write!(result, ".").unwrap();
+    }
+}
@@ -1,9 +1,16 @@
 mod bib;
+mod citation;
 mod config;
+mod csl;
+mod diagnostic;
+mod disambiguate;
 mod format;
+mod placeholder;
 mod render;
+mod sort;
 
 use crate::config::Config;
+use crate::diagnostic::Diagnostics;
 use std::convert::TryFrom;
 use std::error::Error;
 use std::path::PathBuf;
@@ -25,7 +32,9 @@ fn run() -> Result<(), Box<dyn Error>> {
 
     check_version(&data.context);
 
-    let bibliography = bib::load_bibliography(&config.bib_file)?;
+    let mut diagnostics = Diagnostics::new(config.levels);
+    let bibliography = bib::load_bibliography(&config, &mut diagnostics)?;
+    let order = render::document_order(&data.documents, &config, &mut diagnostics);
 
     if let Some(refs_file) = &config.refs_file {
         let path = PathBuf::from(&refs_file);
@@ -37,23 +46,70 @@ fn run() -> Result<(), Box<dyn Error>> {
             .into());
         }
 
-        let citations =
-            render::render_citations_all(&mut data.documents, &bibliography, &config, &path);
+        let keys = render::cited_keys(data.documents.values());
+        let bibliography = disambiguate::disambiguate(&bibliography, &keys, &config);
 
-        render::insert_references(
+        let citations = render::render_citations_all(
+            &mut data.documents,
+            &order,
+            &bibliography,
+            &config,
+            &path,
+            &mut diagnostics,
+        );
+
+        let found = render::insert_references(
             &path,
             data.documents.get_mut(&path).unwrap(),
+            &config.placeholder,
+            false,
             &citations,
             &bibliography,
             &config,
-        );
+            &mut diagnostics,
+        )?;
+        render::check_placeholder(&path, found, &citations, &mut diagnostics);
+
+        for doc_path in &order {
+            render::insert_references(
+                doc_path,
+                data.documents.get_mut(doc_path).unwrap(),
+                &config.local_placeholder,
+                true,
+                &citations.for_document(doc_path),
+                &bibliography,
+                &config,
+                &mut diagnostics,
+            )?;
+        }
     } else {
-        for (path, mut doc) in data.documents.iter_mut() {
-            let citations = render::render_citations(&mut doc, &bibliography, &config);
-            render::insert_references(path, &mut doc, &citations, &bibliography, &config);
+        for path in &order {
+            let doc = data.documents.get_mut(path).unwrap();
+            let keys = render::cited_keys(std::iter::once(&*doc));
+            let bibliography = disambiguate::disambiguate(&bibliography, &keys, &config);
+            let citations =
+                render::render_citations(path, doc, &bibliography, &config, &mut diagnostics);
+            // Without a refs-file, both lists contain the references cited in the document,
+            // and citations link to the anchors of the full list
+            let mut found = false;
+            for placeholder in &[&config.placeholder, &config.local_placeholder] {
+                found |= render::insert_references(
+                    path,
+                    doc,
+                    placeholder,
+                    false,
+                    &citations,
+                    &bibliography,
+                    &config,
+                    &mut diagnostics,
+                )?;
+            }
+            render::check_placeholder(path, found, &citations, &mut diagnostics);
         }
     }
 
+    diagnostics.finish()?;
+
     yarner_lib::write_output(&data)?;
     Ok(())
 }
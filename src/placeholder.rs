@@ -0,0 +1,192 @@
+use crate::config::Sort;
+use crate::format;
+use biblatex::Entry;
+use regex::Regex;
+use std::error::Error;
+use std::str::FromStr;
+
+const OPTION_KEYS: &[&str] = &["type", "keyword", "sort", "heading"];
+
+/// A placeholder for a list of references, optionally with options like
+/// `[[_REFS_ type=article sort=year-desc heading="Articles"_]]`.
+pub struct Placeholder {
+    regex: Regex,
+}
+
+impl Placeholder {
+    /// Creates a placeholder from its plain form. Options are accepted
+    /// if the plain form ends with `]]`, and are placed in front of it, followed by `_`.
+    pub fn new(placeholder: &str) -> Self {
+        let pattern = match placeholder.strip_suffix("]]") {
+            Some(open) => format!(r"{}(?:\s+(.*?)_)?\]\]", regex::escape(open)),
+            None => regex::escape(placeholder),
+        };
+        Placeholder {
+            regex: Regex::new(&pattern).unwrap(),
+        }
+    }
+
+    /// Finds the placeholder in a line, and parses its options.
+    pub fn find(&self, line: &str) -> Option<Result<ListOptions, Box<dyn Error>>> {
+        let captures = self.regex.captures(line)?;
+        Some(
+            captures
+                .get(1)
+                .map_or(Ok(ListOptions::default()), |options| {
+                    options.as_str().parse()
+                }),
+        )
+    }
+}
+
+/// Options of a reference list, given in its placeholder.
+#[derive(Debug, Default, PartialEq)]
+pub struct ListOptions {
+    /// Entry types to include, all if empty
+    pub types: Vec<String>,
+    /// Keywords of entries to include, all if empty
+    pub keywords: Vec<String>,
+    pub sort: Option<Sort>,
+    pub heading: Option<String>,
+}
+
+impl ListOptions {
+    /// Whether an entry is included in the list.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let entry_type = entry.entry_type.to_string().to_lowercase();
+        let type_matches = self.types.is_empty() || self.types.contains(&entry_type);
+
+        let keywords: Vec<_> = entry
+            .get("keywords")
+            .map(format::format_chunk)
+            .unwrap_or_default()
+            .split(&[',', ';'][..])
+            .map(|keyword| keyword.trim().to_lowercase())
+            .collect();
+        let keyword_matches = self.keywords.is_empty()
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| keywords.contains(keyword));
+
+        type_matches && keyword_matches
+    }
+
+    /// The heading line of the list, if any. Headings without `#` are made level 2 headings.
+    pub fn heading_line(&self) -> Option<String> {
+        self.heading.as_ref().map(|heading| {
+            if heading.starts_with('#') {
+                heading.clone()
+            } else {
+                format!("## {}", heading)
+            }
+        })
+    }
+}
+
+impl FromStr for ListOptions {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = ListOptions::default();
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            let eq = rest
+                .find('=')
+                .filter(|eq| !rest[..*eq].contains(char::is_whitespace))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid placeholder option '{}'. Use key=value",
+                        rest.split_whitespace().next().unwrap_or_default()
+                    )
+                })?;
+            let key = &rest[..eq];
+            rest = &rest[eq + 1..];
+
+            let value = if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted.find('"').ok_or_else(|| {
+                    format!("Missing closing quote in placeholder option '{}'", key)
+                })?;
+                rest = &quoted[end + 1..];
+                &quoted[..end]
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let value = &rest[..end];
+                rest = &rest[end..];
+                value
+            };
+            rest = rest.trim_start();
+
+            let list = || {
+                value
+                    .split(',')
+                    .map(|v| v.trim().to_lowercase())
+                    .filter(|v| !v.is_empty())
+                    .collect::<Vec<_>>()
+            };
+            match key {
+                "type" => options.types.extend(list()),
+                "keyword" => options.keywords.extend(list()),
+                "sort" => options.sort = Some(Sort::from_str(value)?),
+                "heading" => options.heading = Some(value.to_owned()),
+                other => {
+                    return Err(format!(
+                        "Unknown placeholder option '{}'. Use one of {}",
+                        other,
+                        OPTION_KEYS.join(", ")
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ListOptions, Placeholder};
+    use crate::config::{Sort, SortKey};
+
+    #[test]
+    fn find_placeholder() {
+        let placeholder = Placeholder::new("[[_REFS_]]");
+        assert_eq!(
+            placeholder.find("[[_REFS_]]").unwrap().unwrap(),
+            ListOptions::default()
+        );
+        assert!(placeholder.find("[[_LOCAL_REFS_]]").is_none());
+
+        let options = placeholder
+            .find(r#"[[_REFS_ type=article,book keyword=primary sort=year-desc heading="Primary sources"_]]"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            options,
+            ListOptions {
+                types: vec!["article".to_string(), "book".to_string()],
+                keywords: vec!["primary".to_string()],
+                sort: Some(Sort {
+                    key: SortKey::Year,
+                    descending: true
+                }),
+                heading: Some("Primary sources".to_string()),
+            }
+        );
+        assert_eq!(
+            options.heading_line(),
+            Some("## Primary sources".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_options() {
+        let placeholder = Placeholder::new("[[_REFS_]]");
+        assert!(placeholder.find("[[_REFS_ color=red_]]").unwrap().is_err());
+        assert!(placeholder
+            .find(r#"[[_REFS_ heading="Open_]]"#)
+            .unwrap()
+            .is_err());
+        assert!(placeholder.find("[[_REFS_ sort_]]").unwrap().is_err());
+    }
+}
@@ -1,79 +1,308 @@
-use crate::config::{CitationStyle, Config};
-use crate::format;
+use crate::citation::{self, Citation, CitationItem, CitePosition, Locator};
+use crate::config::{Backlinks, CitationStyle, Config, Sort, SortKey};
+use crate::csl;
+use crate::diagnostic::{self, Category, Diagnostics, Position};
+use crate::format::{self, NoteForm};
+use crate::placeholder::{ListOptions, Placeholder};
+use crate::sort;
 use biblatex::Bibliography;
 use linked_hash_map::{Entry, LinkedHashMap};
-use once_cell::sync::Lazy;
-use regex::{Captures, Regex};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::error::Error;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
 use yarner_lib::{Document, Node, TextBlock};
 
-const REF_PATTERN: &str = r##"(-)?@([^\[\]\s\.,;"#'()={}%]+)"##;
-static REF_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(REF_PATTERN).unwrap());
+/// The citations found in one or more documents.
+#[derive(Default)]
+pub struct Citations {
+    /// Index of each cited key, in order of first citation
+    pub indices: LinkedHashMap<String, usize>,
+    /// The places each key was cited, for backlinks
+    pub sites: HashMap<String, Vec<Site>>,
+    /// The document currently processed
+    document: PathBuf,
+    /// Heading of the section currently processed, or the document's name
+    section: String,
+    /// Footnotes of the document currently processed, in the notes style
+    notes: Vec<String>,
+    /// Key of the only reference in the previous footnote, for "Ibid."
+    last_note: Option<String>,
+    /// Key and locator of the only reference in the previous citation, for CSL positions
+    last_cite: Option<(String, Option<Locator>)>,
+}
+
+/// Order of author-year reference lists, and of numbered ones with sorted numbering,
+/// if option `sort` is not given.
+const DEFAULT_SORT: Sort = Sort {
+    key: SortKey::AuthorYearTitle,
+    descending: false,
+};
+
+/// A place a key was cited.
+#[derive(Clone)]
+pub struct Site {
+    pub document: PathBuf,
+    pub anchor: String,
+    /// Heading of the section containing the citation, or the document's name
+    pub section: String,
+}
+
+impl Site {
+    /// The link to the citation from a reference list in document `path`.
+    fn link(&self, path: &Path) -> String {
+        if self.document == path {
+            format!("#{}", self.anchor)
+        } else {
+            format!("{}#{}", relative_link(&self.document, path), self.anchor)
+        }
+    }
+}
+
+impl Citations {
+    /// Creates an empty collection for citations of the given keys. With sorted numbering,
+    /// the keys are numbered up front, by their position in the sorted reference list.
+    fn new(keys: &[String], bibliography: &Bibliography, config: &Config) -> Self {
+        let mut citations = Citations::default();
+        if config.citation_style == CitationStyle::Index && config.numbers.sorted {
+            let mut bib: Vec<(&biblatex::Entry, usize)> = vec![];
+            for key in keys {
+                if let Some(entry) = bibliography.get(key) {
+                    if bib.iter().all(|(other, _)| other.key != entry.key) {
+                        bib.push((entry, bib.len()));
+                    }
+                }
+            }
+            sort_references(
+                &mut bib,
+                config.sort.unwrap_or(DEFAULT_SORT),
+                bibliography,
+                config,
+            );
+            for (index, (entry, _)) in bib.iter().enumerate() {
+                citations.indices.insert(entry.key.clone(), index);
+            }
+        }
+        citations
+    }
 
+    /// Starts collecting citations from a document.
+    fn start_document(&mut self, path: &Path) {
+        self.document = path.to_owned();
+        self.section = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.notes.clear();
+        self.last_note = None;
+        self.last_cite = None;
+    }
+
+    /// Records a citation of a key and returns the number of the citation for this key.
+    fn add_site(&mut self, key: &str) -> usize {
+        let sites = self.sites.entry(key.to_owned()).or_default();
+        let number = sites.len() + 1;
+        sites.push(Site {
+            document: self.document.clone(),
+            anchor: format::site_anchor(key, number),
+            section: self.section.clone(),
+        });
+        number
+    }
+
+    /// How a key is given in a footnote of the notes style, if cited now.
+    /// Ibid. is only used if the key is the only one in this and the previous footnote.
+    fn note_form(&self, key: &str, single: bool) -> NoteForm {
+        if single && self.last_note.as_deref() == Some(key) {
+            NoteForm::Ibid
+        } else if self.cited_in_document(key) {
+            NoteForm::Short
+        } else {
+            NoteForm::Full
+        }
+    }
+
+    /// The position of a key for CSL styles, if cited now.
+    /// Like "Ibid.", ibid positions require the key to be the only one in both citations.
+    fn position(&self, item: &CitationItem, single: bool) -> CitePosition {
+        match &self.last_cite {
+            Some((key, locator)) if single && *key == item.key => {
+                if item.locator.is_none() || *locator == item.locator {
+                    CitePosition::Ibid
+                } else {
+                    CitePosition::IbidWithLocator
+                }
+            }
+            _ if self.cited_in_document(&item.key) => CitePosition::Subsequent,
+            _ => CitePosition::First,
+        }
+    }
+
+    fn cited_in_document(&self, key: &str) -> bool {
+        self.sites
+            .get(key)
+            .into_iter()
+            .flatten()
+            .any(|site| site.document == self.document)
+    }
+
+    /// Adds a footnote to the current document and returns its number.
+    fn add_note(&mut self, note: String) -> usize {
+        self.notes.push(note);
+        self.notes.len()
+    }
+
+    /// The citations in a single document, keeping the indices of all documents.
+    pub fn for_document(&self, path: &Path) -> Citations {
+        let mut result = Citations::default();
+        for (key, index) in &self.indices {
+            let sites: Vec<_> = self
+                .sites
+                .get(key)
+                .into_iter()
+                .flatten()
+                .filter(|site| site.document == path)
+                .cloned()
+                .collect();
+            if !sites.is_empty() {
+                result.indices.insert(key.clone(), *index);
+                result.sites.insert(key.clone(), sites);
+            }
+        }
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// Replaces each placeholder in a document by a list of references, filtered and sorted
+/// according to the placeholder's options. Returns whether any placeholder was found.
+/// A `local` list of the references cited in the document gets anchors of its own.
+#[allow(clippy::too_many_arguments)]
 pub fn insert_references(
-    path: &PathBuf,
+    path: &Path,
     document: &mut Document,
-    citations: &LinkedHashMap<String, usize>,
+    placeholder: &str,
+    local: bool,
+    citations: &Citations,
     bibliography: &Bibliography,
     config: &Config,
-) {
+    diagnostics: &mut Diagnostics,
+) -> Result<bool, Box<dyn Error>> {
     let mut pattern_found = false;
 
+    let placeholder = Placeholder::new(placeholder);
     for node in document.nodes.iter_mut() {
         if let Node::Text(block) = node {
-            for line_idx in 0..block.text.len() {
-                if block.text[line_idx].contains(&config.placeholder) {
-                    let refs = render_references(&citations, &bibliography, &config);
-                    block.text = block
-                        .text
-                        .iter()
-                        .take(line_idx)
-                        .chain(refs.iter())
-                        .chain(block.text.iter().skip(line_idx + 1))
-                        .cloned()
-                        .collect();
-                    pattern_found = true;
-                    break;
+            let mut text = vec![];
+            for line in &block.text {
+                match placeholder.find(line) {
+                    Some(options) => {
+                        let options =
+                            options.map_err(|err| format!("{} (in {})", err, path.display()))?;
+                        text.extend(render_references(
+                            path,
+                            local,
+                            citations,
+                            bibliography,
+                            config,
+                            &options,
+                            diagnostics,
+                        ));
+                        pattern_found = true;
+                    }
+                    None => text.push(line.clone()),
                 }
             }
+            block.text = text;
         }
     }
 
-    if !pattern_found && !citations.is_empty() {
-        eprintln!(
-            "  Warning: no placeholder for references found in {}",
-            path.display()
+    Ok(pattern_found)
+}
+
+/// Reports a missing placeholder in a document with citations.
+pub fn check_placeholder(
+    path: &Path,
+    found: bool,
+    citations: &Citations,
+    diagnostics: &mut Diagnostics,
+) {
+    if !found && !citations.is_empty() {
+        diagnostics.report(
+            Category::MissingPlaceholder,
+            format!("no placeholder for references found in {}", path.display()),
         );
     }
 }
 
 fn render_references(
-    citations: &LinkedHashMap<String, usize>,
+    path: &Path,
+    local: bool,
+    citations: &Citations,
     bibliography: &Bibliography,
     config: &Config,
+    options: &ListOptions,
+    diagnostics: &mut Diagnostics,
 ) -> Vec<String> {
     let mut text = vec![];
 
-    let bib: Vec<_> = match config.citation_style {
+    let mut bib: Vec<_> = match &config.citation_style {
         CitationStyle::Index => citations
+            .indices
             .iter()
-            .filter_map(|(key, idx)| bibliography.get(key).map(|e| (e, idx)))
+            .filter_map(|(key, idx)| bibliography.get(key).map(|e| (e, *idx)))
             .collect(),
-        CitationStyle::AuthorYear => {
+        CitationStyle::AuthorYear | CitationStyle::Notes => {
             let mut bib: Vec<_> = bibliography
                 .iter()
-                .filter_map(|entry| citations.get(&entry.key).map(|idx| (entry, idx)))
+                .filter_map(|entry| citations.indices.get(&entry.key).map(|idx| (entry, *idx)))
                 .collect();
-            bib.sort_by_cached_key(|(entry, _idx)| {
-                (entry.author(), format::format_date(entry.date()))
-            });
+            sort_references(&mut bib, DEFAULT_SORT, bibliography, config);
+            bib
+        }
+        CitationStyle::Csl(style) => {
+            let mut bib: Vec<_> = citations
+                .indices
+                .iter()
+                .filter_map(|(key, idx)| bibliography.get(key).map(|e| (e, *idx)))
+                .collect();
+            csl::sort_entries(style, &mut bib);
             bib
         }
     };
 
+    bib.retain(|(entry, _)| options.matches(entry));
+    if let Some(sort) = options.sort.or(config.sort) {
+        sort_references(&mut bib, sort, bibliography, config);
+    }
+    if bib.is_empty() {
+        return text;
+    }
+    if let Some(heading) = options.heading_line() {
+        text.push(heading);
+        text.push("".to_string());
+    }
+
     for (item, idx) in bib.iter() {
-        text.push(format::format_reference(item, *idx + 1, &config));
+        let is_csl = matches!(config.citation_style, CitationStyle::Csl(_));
+        if !is_csl && !format::is_supported(&item.entry_type) {
+            diagnostics.report(
+                Category::UnsupportedType,
+                format!(
+                    "unsupported entry type '{}' of reference '{}', formatted as misc",
+                    item.entry_type, item.key
+                ),
+            );
+        }
+        let mut reference =
+            format::format_reference(item, idx + 1, Some(path).filter(|_| local), config);
+        if let Some(sites) = citations.sites.get(&item.key) {
+            reference.push_str(&format_backlinks(sites, path, config));
+        }
+        text.push(reference);
         text.push("".to_string());
     }
     text.pop();
@@ -81,47 +310,202 @@ fn render_references(
     text
 }
 
+/// Sorts references by the given key, overriding the style's order.
+/// References with the same key are kept in order of first citation.
+fn sort_references(
+    bib: &mut Vec<(&biblatex::Entry, usize)>,
+    sort: Sort,
+    bibliography: &Bibliography,
+    config: &Config,
+) {
+    let mut keyed: Vec<_> = bib
+        .drain(..)
+        .map(|(entry, idx)| {
+            let value = match sort.key {
+                SortKey::Citation | SortKey::None => None,
+                key => Some(sort::sort_value(entry, key, config)),
+            };
+            let position = match sort.key {
+                SortKey::None => bibliography
+                    .iter()
+                    .position(|other| other.key == entry.key)
+                    .unwrap_or(idx),
+                _ => idx,
+            };
+            (value, position, entry, idx)
+        })
+        .collect();
+    keyed.sort_by(|a, b| match (&a.0, &b.0) {
+        // Ties stay in citation order, also for descending sort orders
+        (Some(value_a), Some(value_b)) => value_a
+            .compare(value_b, sort.descending)
+            .then(a.3.cmp(&b.3)),
+        _ if sort.descending => b.1.cmp(&a.1),
+        _ => a.1.cmp(&b.1),
+    });
+    bib.extend(keyed.into_iter().map(|(_, _, entry, idx)| (entry, idx)));
+}
+
+/// Formats links back to the places a reference was cited, as configured by option `backlinks`.
+fn format_backlinks(sites: &[Site], path: &Path, config: &Config) -> String {
+    let links: Vec<_> = match config.backlinks {
+        Backlinks::None => return String::new(),
+        Backlinks::Numbers => sites
+            .iter()
+            .enumerate()
+            .map(|(idx, site)| format!("[{}]({})", idx + 1, site.link(path)))
+            .collect(),
+        Backlinks::Sections => {
+            let mut sections: Vec<&Site> = vec![];
+            for site in sites {
+                if sections.iter().all(|other| other.section != site.section) {
+                    sections.push(site);
+                }
+            }
+            sections
+                .iter()
+                .map(|site| format!("[{}]({})", site.section, site.link(path)))
+                .collect()
+        }
+    };
+    if links.is_empty() {
+        String::new()
+    } else {
+        format!(" ↩ {}", links.join(", "))
+    }
+}
+
+/// Collects the keys of all citations in the documents, in order of appearance.
+pub fn cited_keys<'a, I>(documents: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a Document>,
+{
+    let mut keys = vec![];
+    for document in documents {
+        for node in &document.nodes {
+            if let Node::Text(block) = node {
+                for line in &block.text {
+                    for (_, citation) in citation::parse_citations(line) {
+                        keys.extend(citation.items.into_iter().map(|item| item.key));
+                    }
+                }
+            }
+        }
+    }
+    keys
+}
+
 pub fn render_citations(
+    path: &Path,
     document: &mut Document,
     bibliography: &Bibliography,
     config: &Config,
-) -> LinkedHashMap<String, usize> {
-    let mut citations = LinkedHashMap::new();
+    diagnostics: &mut Diagnostics,
+) -> Citations {
+    let keys = cited_keys(std::iter::once(&*document));
+    let mut citations = Citations::new(&keys, bibliography, config);
+    citations.start_document(path);
 
-    for mut node in document.nodes.iter_mut() {
+    for (block_idx, mut node) in document.nodes.iter_mut().enumerate() {
         if let Node::Text(block) = &mut node {
-            render_citations_block(block, bibliography, None, config, &mut citations);
+            render_citations_block(
+                block,
+                &Position::new(path, block_idx, 0),
+                bibliography,
+                None,
+                config,
+                &mut citations,
+                diagnostics,
+            );
         }
     }
+    append_notes(document, &mut citations);
 
     citations
 }
 
+/// Appends the footnotes collected from a document in the notes style.
+fn append_notes(document: &mut Document, citations: &mut Citations) {
+    if citations.notes.is_empty() {
+        return;
+    }
+    let mut text = vec![];
+    for (idx, note) in citations.notes.drain(..).enumerate() {
+        text.push(String::new());
+        text.push(format!("{}: {}", format::note_label(idx + 1), note));
+    }
+    document.nodes.push(Node::Text(TextBlock { text }));
+}
+
+/// The order in which documents are processed, and citations are numbered:
+/// documents listed in option `document-order` first, then all others sorted by path.
+pub fn document_order(
+    documents: &HashMap<PathBuf, Document>,
+    config: &Config,
+    diagnostics: &mut Diagnostics,
+) -> Vec<PathBuf> {
+    let normalize = |path: &Path| -> PathBuf {
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .collect()
+    };
+    let mut rest: Vec<_> = documents.keys().cloned().collect();
+    rest.sort();
+
+    let mut order = vec![];
+    for listed in &config.document_order {
+        match rest
+            .iter()
+            .position(|path| normalize(path) == normalize(listed))
+        {
+            Some(idx) => order.push(rest.remove(idx)),
+            None => diagnostics.report(
+                Category::MissingFile,
+                format!(
+                    "document {} in option 'document-order' not found",
+                    listed.display()
+                ),
+            ),
+        }
+    }
+    order.extend(rest);
+    order
+}
+
+/// Renders the citations of all documents, in the given order.
 pub fn render_citations_all(
     documents: &mut HashMap<PathBuf, Document>,
+    order: &[PathBuf],
     bibliography: &Bibliography,
     config: &Config,
     refs_file: &PathBuf,
-) -> LinkedHashMap<String, usize> {
-    let mut citations = LinkedHashMap::new();
+    diagnostics: &mut Diagnostics,
+) -> Citations {
+    let keys = cited_keys(order.iter().map(|path| &documents[path]));
+    let mut citations = Citations::new(&keys, bibliography, config);
 
-    for (path, doc) in documents.iter_mut() {
+    for path in order {
+        let doc = documents.get_mut(path).unwrap();
         let rel_link = if path == refs_file {
             None
         } else {
             Some(relative_link(refs_file, path))
         };
-        for mut node in doc.nodes.iter_mut() {
+        citations.start_document(path);
+        for (block_idx, mut node) in doc.nodes.iter_mut().enumerate() {
             if let Node::Text(block) = &mut node {
                 render_citations_block(
                     block,
+                    &Position::new(path, block_idx, 0),
                     bibliography,
                     rel_link.as_ref(),
                     config,
                     &mut citations,
+                    diagnostics,
                 );
             }
         }
+        append_notes(doc, &mut citations);
     }
 
     citations
@@ -139,50 +523,156 @@ where
 
 fn render_citations_block(
     block: &mut TextBlock,
+    position: &Position,
     bibliography: &Bibliography,
     link_prefix: Option<&String>,
     config: &Config,
-    citations: &mut LinkedHashMap<String, usize>,
+    citations: &mut Citations,
+    diagnostics: &mut Diagnostics,
 ) {
-    for line in block.text.iter_mut() {
-        if REF_REGEX.is_match(&line) {
-            let ln = REF_REGEX.replace_all(line, |caps: &Captures| {
-                let no_author = caps.get(1).is_some();
-                let key = &caps[2];
-                if let Some(reference) = bibliography.get(key) {
-                    let index = citations.len();
-                    let ref_index = match citations.entry(key.to_owned()) {
-                        Entry::Occupied(entry) => *entry.get(),
-                        Entry::Vacant(entry) => *entry.insert(index),
-                    };
-                    format::format_citation(
-                        reference,
-                        ref_index + 1,
-                        link_prefix,
-                        no_author,
-                        config,
-                    )
-                } else {
-                    let original = caps.get(0).unwrap().as_str().to_owned();
-                    eprintln!(
-                        "  Warning: citation key '{}' not found in bibliography.",
-                        original
-                    );
-                    original
-                }
-            });
+    for (line_idx, line) in block.text.iter_mut().enumerate() {
+        if let Some(heading) = heading(line) {
+            citations.section = heading;
+        }
+        let mut found = citation::parse_citations(line);
+        if found.is_empty() {
+            continue;
+        }
+        if config.citation_style == CitationStyle::Index && config.numbers.compress {
+            found = merge_adjacent(line, found);
+        }
+
+        let mut ln = String::new();
+        let mut last = 0;
+        for (range, cit) in found {
+            let mut anchors = String::new();
+            let single = cit.items.len() == 1;
+            let references: Vec<_> = cit
+                .items
+                .iter()
+                .map(|item| {
+                    if let Some(reference) = bibliography.get(&item.key) {
+                        let form = citations.note_form(&item.key, single);
+                        let cite_position = citations.position(item, single);
+                        let index = citations.indices.len();
+                        let ref_index = match citations.indices.entry(item.key.clone()) {
+                            Entry::Occupied(entry) => *entry.get(),
+                            Entry::Vacant(entry) => *entry.insert(index),
+                        };
+                        let number = citations.add_site(&item.key);
+                        if config.backlinks != Backlinks::None {
+                            anchors.push_str(&format::format_site_anchor(&item.key, number));
+                        }
+                        Some((reference, ref_index + 1, form, cite_position))
+                    } else {
+                        let suggestion = diagnostic::suggest(
+                            &item.key,
+                            bibliography.iter().map(|entry| entry.key.as_str()),
+                        );
+                        diagnostics.report(
+                            Category::UnknownKey,
+                            format!(
+                                "citation key '{}' not found in bibliography ({}).{}",
+                                item.original(),
+                                position.at_line(line_idx),
+                                diagnostic::did_you_mean(suggestion),
+                            ),
+                        );
+                        None
+                    }
+                })
+                .collect();
 
-            *line = ln.to_string();
+            citations.last_cite = match (cit.items.as_slice(), references.as_slice()) {
+                ([item], [Some(_)]) => Some((item.key.clone(), item.locator.clone())),
+                _ => None,
+            };
+
+            ln.push_str(&line[last..range.start]);
+            ln.push_str(&anchors);
+            if config.citation_style == CitationStyle::Notes {
+                let references: Vec<_> = references
+                    .iter()
+                    .map(|reference| reference.map(|(entry, index, form, _)| (entry, index, form)))
+                    .collect();
+                let note = format::format_note(&cit, &references, link_prefix, config);
+                let number = citations.add_note(note);
+                citations.last_note = match references.as_slice() {
+                    [Some((entry, _, _))] => Some(entry.key.clone()),
+                    _ => None,
+                };
+                ln.push_str(&format::format_note_citation(
+                    &cit,
+                    &references,
+                    number,
+                    config,
+                ));
+            } else {
+                let references: Vec<_> = references
+                    .iter()
+                    .map(|reference| {
+                        reference.map(|(entry, index, _, position)| (entry, index, position))
+                    })
+                    .collect();
+                ln.push_str(&format::format_citation(
+                    &cit,
+                    &references,
+                    link_prefix,
+                    config,
+                ));
+            }
+            last = range.end;
+        }
+        ln.push_str(&line[last..]);
+
+        *line = ln;
+    }
+}
+
+/// Merges citations separated by whitespace only, like `@a @b`, into a bracketed group.
+fn merge_adjacent(
+    line: &str,
+    found: Vec<(Range<usize>, Citation)>,
+) -> Vec<(Range<usize>, Citation)> {
+    let mut merged: Vec<(Range<usize>, Citation)> = vec![];
+    for (range, citation) in found {
+        match merged.last_mut() {
+            Some((last, group)) if line[last.end..range.start].trim().is_empty() => {
+                last.end = range.end;
+                group.items.extend(citation.items);
+                group.bracketed = true;
+            }
+            _ => merged.push((range, citation)),
         }
     }
+    merged
+}
+
+/// The text of an ATX heading like `## Methods`.
+fn heading(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 || line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim().trim_end_matches('#').trim_end().to_owned()).filter(|text| !text.is_empty())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::config::{CitationStyle, Config};
+    use crate::citation::{parse_citations, CitePosition};
+    use crate::config::{Backlinks, CitationStyle, Config, Level};
+    use crate::diagnostic::{Diagnostics, Position};
+    use crate::placeholder::ListOptions;
+    use crate::render::Citations;
     use biblatex::Bibliography;
-    use linked_hash_map::LinkedHashMap;
-    use yarner_lib::TextBlock;
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use yarner_lib::{Document, Node, TextBlock};
 
     const TEST_BIB: &str = r#"
 @book{Klabnik2018,
@@ -197,24 +687,28 @@ mod test {
     #[test]
     fn render_citations_block() {
         let config = Config {
-            bib_file: "".to_string(),
             citation_style: CitationStyle::AuthorYear,
-            refs_file: None,
-            placeholder: "[[_REFS_]]".to_string(),
-            render_key: true,
-            link_refs: true,
+            ..Config::default()
         };
 
         let bib = Bibliography::parse(TEST_BIB).unwrap();
-        let mut citations = LinkedHashMap::new();
+        let mut citations = Citations::default();
 
         let mut block = TextBlock {
             text: vec!["A test citation: @Klabnik2018.".to_string()],
         };
 
-        super::render_citations_block(&mut block, &bib, None, &config, &mut citations);
+        super::render_citations_block(
+            &mut block,
+            &Position::new(Path::new("test.md"), 0, 0),
+            &bib,
+            None,
+            &config,
+            &mut citations,
+            &mut Diagnostics::new(config.levels),
+        );
 
-        assert_eq!(citations.len(), 1);
+        assert_eq!(citations.indices.len(), 1);
         assert_eq!(
             &block.text[0],
             "A test citation: [Klabnik & Nichols 2018](#cite-ref-Klabnik2018)."
@@ -224,27 +718,450 @@ mod test {
     #[test]
     fn render_citations_block_no_author() {
         let config = Config {
-            bib_file: "".to_string(),
             citation_style: CitationStyle::AuthorYear,
-            refs_file: None,
-            placeholder: "[[_REFS_]]".to_string(),
-            render_key: true,
-            link_refs: true,
+            ..Config::default()
         };
 
         let bib = Bibliography::parse(TEST_BIB).unwrap();
-        let mut citations = LinkedHashMap::new();
+        let mut citations = Citations::default();
 
         let mut block = TextBlock {
             text: vec!["A test citation: -@Klabnik2018.".to_string()],
         };
 
-        super::render_citations_block(&mut block, &bib, None, &config, &mut citations);
+        super::render_citations_block(
+            &mut block,
+            &Position::new(Path::new("test.md"), 0, 0),
+            &bib,
+            None,
+            &config,
+            &mut citations,
+            &mut Diagnostics::new(config.levels),
+        );
 
-        assert_eq!(citations.len(), 1);
+        assert_eq!(citations.indices.len(), 1);
         assert_eq!(
             &block.text[0],
             "A test citation: [2018](#cite-ref-Klabnik2018)."
         )
     }
+
+    #[test]
+    fn render_citations_block_group() {
+        let config = Config {
+            citation_style: CitationStyle::Index,
+            ..Config::default()
+        };
+
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let mut citations = Citations::default();
+
+        let mut block = TextBlock {
+            text: vec!["A test citation [see @Klabnik2018, ch. 3].".to_string()],
+        };
+
+        super::render_citations_block(
+            &mut block,
+            &Position::new(Path::new("test.md"), 0, 0),
+            &bib,
+            None,
+            &config,
+            &mut citations,
+            &mut Diagnostics::new(config.levels),
+        );
+
+        assert_eq!(citations.indices.len(), 1);
+        assert_eq!(
+            &block.text[0],
+            "A test citation [see [1](#cite-ref-Klabnik2018), chap. 3]."
+        )
+    }
+
+    #[test]
+    fn backlinks() {
+        let mut config = Config {
+            citation_style: CitationStyle::Index,
+            render_key: false,
+            backlinks: Backlinks::Numbers,
+            ..Config::default()
+        };
+
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let text = [
+            "# Introduction",
+            "As shown by @Klabnik2018.",
+            "## Methods ##",
+            "See @Klabnik2018.",
+            "[[_REFS_]]",
+        ];
+        let mut document = Document {
+            nodes: vec![Node::Text(TextBlock {
+                text: text.iter().map(|line| line.to_string()).collect(),
+            })],
+            newline: "\n".to_string(),
+        };
+        let path = PathBuf::from("test.md");
+        let mut diagnostics = Diagnostics::new(config.levels);
+        let citations =
+            super::render_citations(&path, &mut document, &bib, &config, &mut diagnostics);
+
+        let lines = match &document.nodes[0] {
+            Node::Text(block) => block.text.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            lines[1],
+            "As shown by <a name=\"cite-Klabnik2018-1\" id=\"cite-Klabnik2018-1\"></a>\
+             [1](#cite-ref-Klabnik2018)."
+        );
+
+        let refs = super::render_references(
+            &path,
+            false,
+            &citations,
+            &bib,
+            &config,
+            &ListOptions::default(),
+            &mut diagnostics,
+        );
+        assert!(refs[0]
+            .ends_with("No Starch Press*. ↩ [1](#cite-Klabnik2018-1), [2](#cite-Klabnik2018-2)"));
+
+        config.backlinks = Backlinks::Sections;
+        let refs = super::render_references(
+            &path,
+            false,
+            &citations,
+            &bib,
+            &config,
+            &ListOptions::default(),
+            &mut diagnostics,
+        );
+        assert!(refs[0].ends_with(
+            "No Starch Press*. ↩ [Introduction](#cite-Klabnik2018-1), [Methods](#cite-Klabnik2018-2)"
+        ));
+    }
+
+    #[test]
+    fn local_and_global_references() {
+        let config = Config {
+            citation_style: CitationStyle::Index,
+            render_key: false,
+            backlinks: Backlinks::Numbers,
+            ..Config::default()
+        };
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let document = |text: &[&str]| Document {
+            nodes: vec![Node::Text(TextBlock {
+                text: text.iter().map(|line| line.to_string()).collect(),
+            })],
+            newline: "\n".to_string(),
+        };
+        let text = |document: &Document| match &document.nodes[0] {
+            Node::Text(block) => block.text.clone(),
+            _ => unreachable!(),
+        };
+
+        let chapter = PathBuf::from("chapter.md");
+        let refs = PathBuf::from("refs.md");
+        let mut documents = HashMap::new();
+        documents.insert(
+            chapter.clone(),
+            document(&["See @Klabnik2018.", "[[_LOCAL_REFS_]]"]),
+        );
+        documents.insert(refs.clone(), document(&["[[_REFS_]]"]));
+
+        let mut diagnostics = Diagnostics::new(config.levels);
+        let order = super::document_order(&documents, &config, &mut diagnostics);
+        let citations = super::render_citations_all(
+            &mut documents,
+            &order,
+            &bib,
+            &config,
+            &refs,
+            &mut diagnostics,
+        );
+
+        let found = super::insert_references(
+            &refs,
+            documents.get_mut(&refs).unwrap(),
+            &config.placeholder,
+            false,
+            &citations,
+            &bib,
+            &config,
+            &mut diagnostics,
+        )
+        .unwrap();
+        assert!(found);
+        let found = super::insert_references(
+            &chapter,
+            documents.get_mut(&chapter).unwrap(),
+            &config.local_placeholder,
+            true,
+            &citations.for_document(&chapter),
+            &bib,
+            &config,
+            &mut diagnostics,
+        )
+        .unwrap();
+        assert!(found);
+
+        let chapter_text = text(&documents[&chapter]);
+        assert!(chapter_text[0].ends_with("[1](refs.md#cite-ref-Klabnik2018)."));
+        assert!(chapter_text[1].starts_with(
+            "<a name=\"cite-ref-chapter-Klabnik2018\" id=\"cite-ref-chapter-Klabnik2018\"></a>[1] "
+        ));
+        assert!(chapter_text[1].ends_with(" ↩ [1](#cite-Klabnik2018-1)"));
+        let refs_text = text(&documents[&refs]);
+        assert!(refs_text[0].starts_with("<a name=\"cite-ref-Klabnik2018\""));
+        assert!(refs_text[0].ends_with(" ↩ [1](chapter.md#cite-Klabnik2018-1)"));
+    }
+
+    #[test]
+    fn document_order() {
+        let mut config = Config {
+            document_order: vec![PathBuf::from("./c.md"), PathBuf::from("x.md")],
+            ..Config::default()
+        };
+        config.levels.missing_file = Level::Error;
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let document = |text: &str| Document {
+            nodes: vec![Node::Text(TextBlock {
+                text: vec![text.to_string()],
+            })],
+            newline: "\n".to_string(),
+        };
+
+        let mut documents = HashMap::new();
+        for name in &["b.md", "a.md", "c.md"] {
+            documents.insert(PathBuf::from(name), document("See @Klabnik2018."));
+        }
+        let mut diagnostics = Diagnostics::new(config.levels);
+        let order = super::document_order(&documents, &config, &mut diagnostics);
+        assert_eq!(
+            order,
+            vec![
+                PathBuf::from("c.md"),
+                PathBuf::from("a.md"),
+                PathBuf::from("b.md")
+            ]
+        );
+
+        let refs = PathBuf::from("a.md");
+        let citations = super::render_citations_all(
+            &mut documents,
+            &order,
+            &bib,
+            &config,
+            &refs,
+            &mut diagnostics,
+        );
+        let documents: Vec<_> = citations.sites["Klabnik2018"]
+            .iter()
+            .map(|site| site.document.clone())
+            .collect();
+        assert_eq!(
+            documents,
+            vec![
+                PathBuf::from("c.md"),
+                PathBuf::from("a.md"),
+                PathBuf::from("b.md")
+            ]
+        );
+        assert_eq!(
+            diagnostics.finish().unwrap_err().to_string(),
+            "1 problem(s) found:\n  document x.md in option 'document-order' not found"
+        );
+    }
+
+    #[test]
+    fn cite_positions() {
+        let mut citations = Citations::default();
+        citations.start_document(Path::new("test.md"));
+        let position = |citations: &mut Citations, text: &str| {
+            let (_, citation) = parse_citations(text).pop().unwrap();
+            let single = citation.items.len() == 1;
+            let item = &citation.items[0];
+            let position = citations.position(item, single);
+            for item in &citation.items {
+                citations.add_site(&item.key);
+            }
+            citations.last_cite = match citation.items.as_slice() {
+                [item] => Some((item.key.clone(), item.locator.clone())),
+                _ => None,
+            };
+            position
+        };
+
+        assert_eq!(position(&mut citations, "[@a, p. 3]"), CitePosition::First);
+        assert_eq!(position(&mut citations, "[@a, p. 3]"), CitePosition::Ibid);
+        assert_eq!(position(&mut citations, "[@a]"), CitePosition::Ibid);
+        assert_eq!(
+            position(&mut citations, "[@a, p. 4]"),
+            CitePosition::IbidWithLocator
+        );
+        assert_eq!(
+            position(&mut citations, "[@a; @b]"),
+            CitePosition::Subsequent
+        );
+        assert_eq!(position(&mut citations, "[@a]"), CitePosition::Subsequent);
+        assert_eq!(position(&mut citations, "[@b]"), CitePosition::Subsequent);
+
+        citations.start_document(Path::new("other.md"));
+        assert_eq!(position(&mut citations, "[@a]"), CitePosition::First);
+    }
+
+    #[test]
+    fn notes() {
+        let config = Config {
+            citation_style: CitationStyle::Notes,
+            ..Config::default()
+        };
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let mut document = Document {
+            nodes: vec![Node::Text(TextBlock {
+                text: vec![
+                    "See [@Klabnik2018, p. 12].".to_string(),
+                    "Again [@Klabnik2018, p. 14].".to_string(),
+                    "Both [@Klabnik2018; @Unknown].".to_string(),
+                    "Finally @Klabnik2018.".to_string(),
+                ],
+            })],
+            newline: "\n".to_string(),
+        };
+
+        let path = PathBuf::from("test.md");
+        let mut diagnostics = Diagnostics::new(config.levels);
+        super::render_citations(&path, &mut document, &bib, &config, &mut diagnostics);
+
+        let text = |node: &Node| match node {
+            Node::Text(block) => block.text.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            text(&document.nodes[0]),
+            vec![
+                "See [^cite-1].",
+                "Again [^cite-2].",
+                "Both [^cite-3].",
+                "Finally Klabnik & Nichols[^cite-4].",
+            ]
+        );
+
+        let notes = text(&document.nodes[1]);
+        assert_eq!(notes.len(), 8);
+        assert!(notes[1].starts_with("[^cite-1]: Klabnik"));
+        assert!(notes[1].contains("**The Rust Programming Language**"));
+        assert!(notes[1].ends_with(", 12."));
+        assert_eq!(notes[3], "[^cite-2]: Ibid., 14.");
+        assert_eq!(
+            notes[5],
+            "[^cite-3]: [Klabnik & Nichols, *The Rust Programming Language*](#cite-ref-Klabnik2018); @Unknown."
+        );
+        assert_eq!(
+            notes[7],
+            "[^cite-4]: [Klabnik & Nichols, *The Rust Programming Language*](#cite-ref-Klabnik2018)."
+        );
+    }
+
+    #[test]
+    fn merge_adjacent() {
+        let config = Config {
+            citation_style: CitationStyle::Index,
+            ..Config::default()
+        };
+        let bib = Bibliography::parse(TEST_BIB).unwrap();
+        let mut block = TextBlock {
+            text: vec!["See @Klabnik2018 [@Klabnik2018], and @Klabnik2018.".to_string()],
+        };
+        let mut diagnostics = Diagnostics::new(config.levels);
+        super::render_citations_block(
+            &mut block,
+            &Position::new(Path::new("test.md"), 0, 0),
+            &bib,
+            None,
+            &config,
+            &mut Citations::default(),
+            &mut diagnostics,
+        );
+        assert_eq!(
+            block.text[0],
+            "See [[1](#cite-ref-Klabnik2018)], and [1](#cite-ref-Klabnik2018)."
+        );
+    }
+
+    #[test]
+    fn sorted_numbering() {
+        let mut config = Config {
+            citation_style: CitationStyle::Index,
+            render_key: false,
+            link_refs: false,
+            ..Config::default()
+        };
+        config.numbers.sorted = true;
+        let bib = Bibliography::parse(
+            r#"
+@book{Zed2001, author = {Zed, Zoe}, title = {Last}, year = {2001}}
+@book{Adams2010, author = {Adams, Ann}, title = {First}, year = {2010}}
+"#,
+        )
+        .unwrap();
+        let mut document = Document {
+            nodes: vec![Node::Text(TextBlock {
+                text: vec!["See @Zed2001, and @Adams2010.".to_string()],
+            })],
+            newline: "\n".to_string(),
+        };
+        let path = PathBuf::from("test.md");
+        let mut diagnostics = Diagnostics::new(config.levels);
+        let citations =
+            super::render_citations(&path, &mut document, &bib, &config, &mut diagnostics);
+
+        match &document.nodes[0] {
+            Node::Text(block) => assert_eq!(block.text[0], "See 2, and 1."),
+            _ => unreachable!(),
+        }
+        let references = |config: &Config| {
+            super::render_references(
+                &path,
+                false,
+                &citations,
+                &bib,
+                config,
+                &ListOptions::default(),
+                &mut Diagnostics::new(config.levels),
+            )
+        };
+        let text = references(&config);
+        assert!(text[0].starts_with("[1] Adams A (2010)"));
+        assert!(text[2].starts_with("[2] Zed Z (2001)"));
+
+        config.sort = Some("year-desc".parse().unwrap());
+        let text = references(&config);
+        assert!(text[0].starts_with("[1] Adams A (2010)"));
+        config.sort = Some("none".parse().unwrap());
+        let text = references(&config);
+        assert!(text[0].starts_with("[2] Zed Z (2001)"));
+    }
+
+    #[test]
+    fn sort_descending_ties() {
+        let config = Config::default();
+        let bib = Bibliography::parse(
+            r#"
+@book{Adams2010, author = {Adams, Ann}, title = {First}, year = {2010}}
+@book{Zed2001, author = {Zed, Zoe}, title = {Last}, year = {2001}}
+@book{Young2010, author = {Young, Yan}, title = {Middle}, year = {2010}}
+"#,
+        )
+        .unwrap();
+        let mut entries: Vec<_> = bib.iter().enumerate().map(|(idx, e)| (e, idx)).collect();
+        super::sort_references(&mut entries, "year-desc".parse().unwrap(), &bib, &config);
+        let keys: Vec<_> = entries
+            .iter()
+            .map(|(entry, _)| entry.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["Adams2010", "Young2010", "Zed2001"]);
+    }
 }
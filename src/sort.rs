@@ -0,0 +1,263 @@
+use crate::config::{Config, SortKey};
+use crate::format::{self, latex};
+use biblatex::{DateValue, Entry, Person};
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// A `presort` value and the values of the sorted fields of an entry.
+#[derive(Debug, PartialEq)]
+pub struct SortValue {
+    /// The `presort` value, which is always sorted in ascending order
+    presort: String,
+    fields: Vec<SortField>,
+}
+
+/// A part of a sort value.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortField {
+    Text(String),
+    /// A year, or `None` for undated entries
+    Year(Option<i32>),
+}
+
+impl SortValue {
+    /// Compares two sort values, with the fields in descending order if `descending`.
+    /// Undated entries come last, in either order.
+    pub fn compare(&self, other: &SortValue, descending: bool) -> Ordering {
+        self.presort.cmp(&other.presort).then_with(|| {
+            for (a, b) in self.fields.iter().zip(&other.fields) {
+                let order = match (a, b) {
+                    (SortField::Year(None), SortField::Year(Some(_))) => Ordering::Greater,
+                    (SortField::Year(Some(_)), SortField::Year(None)) => Ordering::Less,
+                    _ if descending => b.cmp(a),
+                    _ => a.cmp(b),
+                };
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            Ordering::Equal
+        })
+    }
+}
+
+/// The value an entry is sorted by, for sort keys that depend on fields.
+///
+/// Follows biblatex: field `presort` comes first, and field `sortkey` replaces all other values.
+/// Names are taken from `sortname`, titles from `sorttitle` and years from `sortyear`, if given.
+/// Entries without names are sorted by their title in place of the names.
+pub fn sort_value(entry: &Entry, key: SortKey, config: &Config) -> SortValue {
+    let presort = entry
+        .get("presort")
+        .map(format::format_chunk)
+        .map(|presort| collate(&presort))
+        .unwrap_or_else(|| "mm".to_string());
+    if let Some(sortkey) = entry.get("sortkey").map(format::format_chunk) {
+        return SortValue {
+            presort,
+            fields: vec![SortField::Text(collate(&sortkey))],
+        };
+    }
+
+    let names = || SortField::Text(names(entry, config).unwrap_or_else(|| title(entry)));
+    let title = || SortField::Text(title(entry));
+    let fields = match key {
+        SortKey::Citation | SortKey::None => vec![],
+        SortKey::Author => vec![names()],
+        SortKey::AuthorYearTitle => vec![names(), year(entry), title()],
+        SortKey::Year => vec![year(entry)],
+        SortKey::Title => vec![title()],
+        SortKey::Type => vec![
+            SortField::Text(entry.entry_type.to_string().to_lowercase()),
+            names(),
+            year(entry),
+            title(),
+        ],
+    };
+    SortValue { presort, fields }
+}
+
+/// The names to sort by: `sortname`, or else the authors or editors, or else the organization.
+fn names(entry: &Entry, config: &Config) -> Option<String> {
+    let persons = entry
+        .get_as::<Vec<Person>>("sortname")
+        .filter(|persons| !persons.is_empty())
+        .or_else(|| format::creator_names(entry));
+    match persons {
+        Some(persons) => Some(
+            persons
+                .iter()
+                .map(|person| {
+                    let family = if config.names.sort_prefix && !person.prefix.is_empty() {
+                        format!("{} {}", person.prefix, person.name)
+                    } else {
+                        person.name.clone()
+                    };
+                    collate(&latex::to_markdown(&format!(
+                        "{} {}",
+                        family, person.given_name
+                    )))
+                })
+                .collect::<Vec<_>>()
+                // Two spaces, so that names are compared one by one
+                .join("  "),
+        ),
+        None => entry
+            .get("organization")
+            .map(|organization| collate(&format::format_chunk(organization))),
+    }
+}
+
+/// The year to sort by, compared as a number: `sortyear`, or else the (first) year of the date.
+fn year(entry: &Entry) -> SortField {
+    let number = |field: &str| {
+        entry
+            .get(field)
+            .and_then(|year| format::format_chunk(year).trim().parse().ok())
+    };
+    let year = number("sortyear")
+        .or_else(|| {
+            entry.date().map(|date| match date.value {
+                DateValue::At(time)
+                | DateValue::After(time)
+                | DateValue::Before(time)
+                | DateValue::Between(time, _) => time.year,
+            })
+        })
+        // Years with less than four digits are not parsed as dates
+        .or_else(|| number("year"));
+    SortField::Year(year)
+}
+
+fn title(entry: &Entry) -> String {
+    entry
+        .get("sorttitle")
+        .or_else(|| entry.title())
+        .map(|title| collate(&format::format_chunk(title)))
+        .unwrap_or_default()
+}
+
+/// A collation key that ignores case, diacritics and punctuation, like `angstrom` for `Ångström`.
+pub fn collate(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.nfd().filter(|c| !is_combining_mark(*c)) {
+        for c in c.to_lowercase() {
+            match c {
+                'ß' => result.push_str("ss"),
+                'æ' => result.push_str("ae"),
+                'œ' => result.push_str("oe"),
+                'þ' => result.push_str("th"),
+                'ø' => result.push('o'),
+                'ł' => result.push('l'),
+                'đ' | 'ð' => result.push('d'),
+                'ı' => result.push('i'),
+                c if c.is_alphanumeric() => result.push(c),
+                c if c.is_whitespace() && !result.ends_with(' ') && !result.is_empty() => {
+                    result.push(' ')
+                }
+                _ => {}
+            }
+        }
+    }
+    result.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{collate, sort_value};
+    use crate::config::{Config, SortKey};
+    use biblatex::Bibliography;
+
+    #[test]
+    fn collation() {
+        assert_eq!(collate("Ångström"), "angstrom");
+        assert_eq!(collate("*The  Straße*, Vol. 2"), "the strasse vol 2");
+        assert_eq!(collate("Œuvres Łódź"), "oeuvres lodz");
+    }
+
+    #[test]
+    fn sort_values() {
+        let bib = Bibliography::parse(
+            r#"
+@book{Gaulle1954, author = {de Gaulle, Charles}, title = {M{\'e}moires}, year = {1954}}
+@book{Angstrom1868, author = {{\AA}ngstr{\"o}m, Anders}, title = {Recherches}, year = {1868}}
+@book{Becker2000, author = {becker, bob}, title = {Lower}, year = {2000}}
+@book{Anon1900, title = {Fabliaux}, year = {1900}}
+@book{Zeta2010, author = {Zeta, Zoe}, sortname = {Aaron, Zoe}, title = {Z}, year = {2010}}
+@book{Last2020, author = {Adams, Ann}, presort = {zz}, title = {Appendix}, year = {2020}}
+"#,
+        )
+        .unwrap();
+        let sorted = |config: &Config| {
+            let mut entries: Vec<_> = bib.iter().collect();
+            entries.sort_by(|a, b| {
+                sort_value(a, SortKey::AuthorYearTitle, config)
+                    .compare(&sort_value(b, SortKey::AuthorYearTitle, config), false)
+            });
+            entries
+                .iter()
+                .map(|entry| entry.key.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut config = Config::default();
+        assert_eq!(
+            sorted(&config),
+            vec![
+                "Zeta2010",
+                "Angstrom1868",
+                "Becker2000",
+                "Anon1900",
+                "Gaulle1954",
+                "Last2020"
+            ]
+        );
+
+        config.names.sort_prefix = true;
+        assert_eq!(
+            sorted(&config),
+            vec![
+                "Zeta2010",
+                "Angstrom1868",
+                "Becker2000",
+                "Gaulle1954",
+                "Anon1900",
+                "Last2020"
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_years() {
+        let bib = Bibliography::parse(
+            r#"
+@book{Last2020, author = {Adams, Ann}, presort = {zz}, year = {2020}}
+@book{NoDate, author = {Baker, Bob}}
+@book{Old999, author = {Cole, Cid}, year = {999}}
+@book{New2010, author = {Dunn, Dan}, year = {2010}}
+"#,
+        )
+        .unwrap();
+        let config = Config::default();
+        let sorted = |descending: bool| {
+            let mut entries: Vec<_> = bib.iter().collect();
+            entries.sort_by(|a, b| {
+                sort_value(a, SortKey::Year, &config)
+                    .compare(&sort_value(b, SortKey::Year, &config), descending)
+            });
+            entries
+                .iter()
+                .map(|entry| entry.key.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sorted(false),
+            vec!["Old999", "New2010", "NoDate", "Last2020"]
+        );
+        assert_eq!(
+            sorted(true),
+            vec!["New2010", "Old999", "NoDate", "Last2020"]
+        );
+    }
+}